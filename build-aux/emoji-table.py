@@ -0,0 +1,143 @@
+#!/usr/bin/env python3
+"""Generates the emoji tables in data/emoji/ from Unicode and CLDR data.
+
+Usage: emoji-table.py EMOJI_TEST_TXT CLDR_DIR LANG [LANG...]
+
+EMOJI_TEST_TXT is emoji-test.txt of the Unicode emoji data and CLDR_DIR a
+checkout of https://github.com/unicode-org/cldr. For every language, the
+annotations of common/annotations/LANG.xml and
+common/annotationsDerived/LANG.xml are written to data/emoji/LANG.tsv as
+
+    glyph <TAB> name <TAB> keyword | keyword ... [<TAB> t]
+
+in the order of emoji-test.txt. The last column is only written to en.tsv
+and marks emoji that take a skin tone modifier. Emoji without annotations
+in a language are left out of its table; the launcher falls back to the
+English names for them.
+
+The emoji are followed by the characters of SYMBOL_BLOCKS that are not
+emoji, in code point order. CLDR annotates the common ones; en.tsv names
+the others after the Unicode character database of the running Python,
+with the block and the code point as keywords.
+"""
+
+import os
+import sys
+import unicodedata
+import xml.etree.ElementTree as ET
+
+SKIN_TONES = {chr(c) for c in range(0x1F3FB, 0x1F400)}
+# variation selector 16, which annotations leave out
+VS16 = "\ufe0f"
+
+SYMBOL_BLOCKS = [
+    (0x00A1, 0x00BF, "Latin-1 Supplement"),
+    (0x00D7, 0x00D7, "Latin-1 Supplement"),
+    (0x00F7, 0x00F7, "Latin-1 Supplement"),
+    (0x0391, 0x03C9, "Greek"),
+    (0x2010, 0x205E, "General Punctuation"),
+    (0x2070, 0x209F, "Superscripts and Subscripts"),
+    (0x20A0, 0x20CF, "Currency Symbols"),
+    (0x2100, 0x214F, "Letterlike Symbols"),
+    (0x2150, 0x218F, "Number Forms"),
+    (0x2190, 0x21FF, "Arrows"),
+    (0x2200, 0x22FF, "Mathematical Operators"),
+    (0x2300, 0x23FF, "Miscellaneous Technical"),
+    (0x2460, 0x24FF, "Enclosed Alphanumerics"),
+    (0x2500, 0x257F, "Box Drawing"),
+    (0x2580, 0x259F, "Block Elements"),
+    (0x25A0, 0x25FF, "Geometric Shapes"),
+    (0x2600, 0x26FF, "Miscellaneous Symbols"),
+    (0x2700, 0x27BF, "Dingbats"),
+    (0x27C0, 0x27EF, "Miscellaneous Mathematical Symbols-A"),
+    (0x27F0, 0x27FF, "Supplemental Arrows-A"),
+    (0x2900, 0x297F, "Supplemental Arrows-B"),
+    (0x2980, 0x29FF, "Miscellaneous Mathematical Symbols-B"),
+    (0x2A00, 0x2AFF, "Supplemental Mathematical Operators"),
+    (0x2B00, 0x2BFF, "Miscellaneous Symbols and Arrows"),
+]
+# unassigned, control, format, space and combining characters
+SKIPPED_CATEGORIES = {"Cn", "Cc", "Cf", "Co", "Zs", "Zl", "Zp", "Mn", "Me"}
+
+
+def fully_qualified(path):
+    """Fully qualified emoji in order, and the ones with skin tone variants."""
+    order, toned = [], set()
+    with open(path, encoding="utf-8") as f:
+        for line in f:
+            line = line.split("#", 1)
+            fields = line[0].split(";")
+            if len(fields) != 2 or fields[1].strip() != "fully-qualified":
+                continue
+            glyph = "".join(chr(int(c, 16)) for c in fields[0].split())
+            base = "".join(c for c in glyph if c not in SKIN_TONES)
+            if base != glyph:
+                toned.add(base.replace(VS16, ""))
+            else:
+                order.append(glyph)
+    return order, toned
+
+
+def symbols(emoji):
+    """The characters of SYMBOL_BLOCKS that are not emoji, with their block."""
+    emoji = {glyph.replace(VS16, "") for glyph in emoji}
+    for first, last, block in SYMBOL_BLOCKS:
+        for cp in range(first, last + 1):
+            c = chr(cp)
+            if c in emoji or unicodedata.category(c) in SKIPPED_CATEGORIES:
+                continue
+            if unicodedata.name(c, ""):
+                yield c, block
+
+
+def annotations(cldr, lang):
+    names, keywords = {}, {}
+    for kind in ("annotations", "annotationsDerived"):
+        path = os.path.join(cldr, "common", kind, lang + ".xml")
+        if not os.path.exists(path):
+            continue
+        for node in ET.parse(path).getroot().iter("annotation"):
+            glyph = node.get("cp").replace(VS16, "")
+            text = (node.text or "").strip()
+            if not text or text == "↑↑↑":
+                continue
+            if node.get("type") == "tts":
+                names[glyph] = text
+            else:
+                keywords[glyph] = text
+    return names, keywords
+
+
+def main():
+    if len(sys.argv) < 4:
+        sys.exit(__doc__)
+    order, toned = fully_qualified(sys.argv[1])
+    out = os.path.join(os.path.dirname(__file__), "..", "data", "emoji")
+    os.makedirs(out, exist_ok=True)
+
+    for lang in sys.argv[3:]:
+        names, keywords = annotations(sys.argv[2], lang)
+        with open(os.path.join(out, lang + ".tsv"), "w", encoding="utf-8") as f:
+            for glyph in order:
+                key = glyph.replace(VS16, "")
+                if key not in names:
+                    continue
+                row = [glyph, names[key], keywords.get(key, "")]
+                if lang == "en" and key in toned:
+                    row.append("t")
+                f.write("\t".join(row) + "\n")
+            for glyph, block in symbols(order):
+                extra = [block.lower(), "u+%04x" % ord(glyph)]
+                if glyph in names:
+                    row = [glyph, names[glyph], keywords.get(glyph, "")]
+                elif lang == "en":
+                    row = [glyph, unicodedata.name(glyph).lower(), ""]
+                else:
+                    continue
+                if lang == "en":
+                    row[2] = " | ".join(filter(None, [row[2]] + extra))
+                f.write("\t".join(row) + "\n")
+
+
+if __name__ == "__main__":
+    main()
@@ -1,2 +1,68 @@
 app-name = Iced Launcher
-translator-credits = Ashley Wulber
\ No newline at end of file
+translator-credits = Ashley Wulber
+
+favorites = Favorites
+search-history = Search previous queries...
+search-clipboard = Search copied text and images...
+search-emoji = Search emoji...
+search-windows = Search open windows...
+run-command = Run a command...
+search-apps = Search applications...
+
+category-all = All
+category-audio-video = Multimedia
+category-development = Development
+category-education = Education
+category-game = Games
+category-graphics = Graphics
+category-network = Internet
+category-office = Office
+category-science = Science
+category-settings = Settings
+category-system = System
+category-utility = Utilities
+
+open-with = Open with {$name}
+open-with-chooser = Open with...
+search-open-with = Open {$name} with...
+open-with-default = Default
+open-with-hint = Ctrl+Enter also makes it the default
+copy = Copy
+window-description = {$app} on workspace {$workspace}
+
+web-open = Open in the browser
+web-search = Search {$engine}
+
+mode-history = History
+mode-clipboard = Clipboard
+mode-emoji = Emoji
+mode-windows = Windows
+mode-run = Run
+mode-apps = Applications
+mode-open-with = Open with
+mode-calculator = Calculator
+mode-web = Web
+mode-files = Files
+mode-recent = Recent files
+mode-ssh = SSH
+mode-scripts = Scripts
+
+preview-type = Type
+preview-size = Size
+preview-modified = Modified
+preview-location = Location
+preview-address = Address
+preview-command = Command
+age-now = Just now
+age-minutes = { $n ->
+    [one] A minute ago
+   *[other] {$n} minutes ago
+}
+age-hours = { $n ->
+    [one] An hour ago
+   *[other] {$n} hours ago
+}
+age-days = { $n ->
+    [one] Yesterday
+   *[other] {$n} days ago
+}
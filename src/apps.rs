@@ -0,0 +1,239 @@
+//! Installed applications, read from the desktop entries in the XDG data
+//! directories.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use freedesktop_desktop_entry::DesktopEntry;
+
+use crate::fuzzy;
+
+/// The main categories of the desktop menu specification, in the order of
+/// the category tabs.
+pub const CATEGORIES: &[&str] = &[
+    "AudioVideo",
+    "Development",
+    "Education",
+    "Game",
+    "Graphics",
+    "Network",
+    "Office",
+    "Science",
+    "Settings",
+    "System",
+    "Utility",
+];
+
+#[derive(Debug, Clone)]
+pub struct App {
+    /// The desktop file ID, such as `org.gnome.Nautilus.desktop`.
+    pub id: String,
+    pub name: String,
+    /// Icon name, or the path of an icon file.
+    pub icon: Option<String>,
+    pub path: PathBuf,
+    /// The main categories the entry is listed in, which may be several,
+    /// such as `AudioVideo` and `Game`.
+    pub categories: Vec<&'static str>,
+    /// The `[Desktop Action]` groups, such as opening a private window.
+    pub actions: Vec<AppAction>,
+    keywords: String,
+    /// The MIME types the application can open.
+    mime_types: Vec<String>,
+    /// Whether the entry is listed in the current desktop. Hidden entries
+    /// are only used to open files.
+    shown: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct AppAction {
+    /// The name of the action in the `Actions` key.
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Apps {
+    /// Sorted by name, including the hidden entries.
+    apps: Vec<App>,
+}
+
+impl Apps {
+    /// Reads the application entries of the XDG data directories, named in
+    /// `language` where they are translated.
+    pub fn load(language: Option<&str>) -> Self {
+        Self::from_dirs(&data_dirs(), language)
+    }
+
+    /// Reads the application entries of the `applications` directories of
+    /// `dirs`, from the most to the least important.
+    pub fn from_dirs(dirs: &[PathBuf], language: Option<&str>) -> Self {
+        let desktops = current_desktops();
+        let mut ids = HashSet::new();
+        let mut apps: Vec<App> = dirs
+            .iter()
+            .flat_map(|dir| entries(&dir.join("applications")))
+            // earlier directories take precedence over the same ID
+            .filter(|(id, _)| ids.insert(id.clone()))
+            .filter_map(|(id, path)| {
+                let bytes = fs::read_to_string(&path).ok()?;
+                let entry = DesktopEntry::decode(&path, &bytes).ok()?;
+                if entry.type_() != Some("Application") || entry.exec().is_none() {
+                    return None;
+                }
+                Some(App {
+                    name: entry.name(language)?.into_owned(),
+                    icon: entry.icon().map(str::to_string),
+                    categories: main_categories(entry.categories()),
+                    actions: actions(&entry, language),
+                    keywords: entry.keywords().unwrap_or_default().replace(';', " "),
+                    mime_types: list(entry.mime_type()),
+                    shown: is_shown(&entry, &desktops),
+                    id,
+                    path,
+                })
+            })
+            .collect();
+        apps.sort_by_cached_key(|app| app.name.to_lowercase());
+        Self { apps }
+    }
+
+    /// The main categories with at least one application.
+    pub fn categories(&self) -> Vec<&'static str> {
+        CATEGORIES
+            .iter()
+            .copied()
+            .filter(|c| self.shown().any(|app| app.categories.contains(c)))
+            .collect()
+    }
+
+    /// The application named `name`, and showing `icon` if one is given,
+    /// which is how the results of the launcher service are told apart.
+    pub fn find(&self, name: &str, icon: Option<&str>) -> Option<&App> {
+        self.shown()
+            .find(|app| app.name == name && (icon.is_none() || app.icon.as_deref() == icon))
+    }
+
+    /// The application with the desktop file ID `id`, even if it is hidden.
+    pub fn get(&self, id: &str) -> Option<&App> {
+        self.apps.iter().find(|app| app.id == id)
+    }
+
+    /// Applications listing `mime` in their `MimeType` key, even if they are
+    /// hidden.
+    pub fn with_mime_type(&self, mime: &str) -> Vec<&App> {
+        self.apps
+            .iter()
+            .filter(|app| app.mime_types.iter().any(|m| m == mime))
+            .collect()
+    }
+
+    /// Applications of `category`, or of all of them, whose name or keywords
+    /// match `query`.
+    pub fn matches(&self, query: &str, category: Option<&str>) -> Vec<&App> {
+        self.shown()
+            .filter(|app| category.map_or(true, |c| app.categories.contains(&c)))
+            .filter(|app| {
+                fuzzy::is_match(query, &app.name) || fuzzy::is_match(query, &app.keywords)
+            })
+            .collect()
+    }
+
+    fn shown(&self) -> impl Iterator<Item = &App> {
+        self.apps.iter().filter(|app| app.shown)
+    }
+}
+
+/// `$XDG_DATA_HOME` followed by `$XDG_DATA_DIRS`.
+pub fn data_dirs() -> Vec<PathBuf> {
+    match xdg::BaseDirectories::new() {
+        Ok(dirs) => std::iter::once(dirs.get_data_home())
+            .chain(dirs.get_data_dirs())
+            .collect(),
+        Err(err) => {
+            log::warn!("failed to find the XDG data directories: {err}");
+            Vec::new()
+        }
+    }
+}
+
+/// The desktop files below `dir` along with their desktop file ID, which
+/// joins the subdirectories with `-`.
+fn entries(dir: &Path) -> Vec<(String, PathBuf)> {
+    let pattern = dir.join("**").join("*.desktop");
+    let paths = match glob::glob(&pattern.to_string_lossy()) {
+        Ok(paths) => paths,
+        Err(_) => return Vec::new(),
+    };
+    paths
+        .flatten()
+        .filter_map(|path| {
+            let relative = path.strip_prefix(dir).ok()?;
+            let id = relative.to_str()?.replace('/', "-");
+            Some((id, path))
+        })
+        .collect()
+}
+
+/// The names of `$XDG_CURRENT_DESKTOP`, such as `pop` and `COSMIC`.
+pub fn current_desktops() -> Vec<String> {
+    std::env::var("XDG_CURRENT_DESKTOP")
+        .unwrap_or_default()
+        .split(':')
+        .filter(|d| !d.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Whether the entry is meant to be listed in the current desktop, going by
+/// `NoDisplay`, `Hidden`, `OnlyShowIn` and `NotShowIn`.
+fn is_shown(entry: &DesktopEntry, desktops: &[String]) -> bool {
+    let listed_in = |list: &str| {
+        list.split(';').any(|d| {
+            desktops
+                .iter()
+                .any(|desktop| desktop.eq_ignore_ascii_case(d))
+        })
+    };
+    !entry.no_display()
+        && entry.desktop_entry("Hidden") != Some("true")
+        && entry.only_show_in().map_or(true, listed_in)
+        && !entry.desktop_entry("NotShowIn").map_or(false, listed_in)
+}
+
+/// The actions of the entry that have a name and a command.
+fn actions(entry: &DesktopEntry, language: Option<&str>) -> Vec<AppAction> {
+    entry
+        .actions()
+        .unwrap_or_default()
+        .split(';')
+        .filter(|id| !id.is_empty() && entry.action_exec(id).is_some())
+        .filter_map(|id| {
+            Some(AppAction {
+                name: entry.action_name(id, language)?.into_owned(),
+                id: id.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// The items of a `;` separated list.
+fn list(value: Option<&str>) -> Vec<String> {
+    value
+        .unwrap_or_default()
+        .split(';')
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// The main categories in the `Categories` key, in the order of the tabs.
+fn main_categories(categories: Option<&str>) -> Vec<&'static str> {
+    let listed = list(categories);
+    CATEGORIES
+        .iter()
+        .copied()
+        .filter(|main| listed.iter().any(|c| c == main))
+        .collect()
+}
@@ -0,0 +1,57 @@
+use crate::config::APP_ID;
+use crate::subscriptions::toggle_dbus::{INTERFACE, OBJECT_PATH};
+
+const USAGE: &str = "Usage: iced-launcher [OPTION]
+
+Options:
+  --mode MODE              open the launcher in MODE: apps, windows, run,
+                           emoji, clipboard or history
+  --clear-history          forget the launch history used for ranking results
+  --clear-query-history    forget the submitted queries recalled with Up/Down
+  -h, --help               print this help";
+
+/// Modes that can be opened with `--mode`, and by prefixes.
+pub const MODES: &[&str] = &["apps", "windows", "run", "emoji", "clipboard", "history"];
+
+/// What the launcher was asked to do on the command line.
+pub enum Command {
+    Run,
+    Open(String),
+    ClearHistory,
+    ClearQueryHistory,
+    Help,
+}
+
+pub fn parse() -> Result<Command, String> {
+    let mut args = std::env::args().skip(1);
+    let command = match args.next().as_deref() {
+        None => Command::Run,
+        Some("--mode") => match args.next() {
+            Some(mode) if MODES.contains(&mode.as_str()) => Command::Open(mode),
+            Some(mode) => return Err(format!("unknown mode '{mode}'\n\n{USAGE}")),
+            None => return Err(format!("missing mode\n\n{USAGE}")),
+        },
+        Some("--clear-history") => Command::ClearHistory,
+        Some("--clear-query-history") => Command::ClearQueryHistory,
+        Some("-h" | "--help") => Command::Help,
+        Some(arg) => return Err(format!("unknown option '{arg}'\n\n{USAGE}")),
+    };
+    match args.next() {
+        Some(arg) => Err(format!("unexpected argument '{arg}'\n\n{USAGE}")),
+        None => Ok(command),
+    }
+}
+
+pub fn usage() {
+    println!("{USAGE}");
+}
+
+/// Calls `method` on an already running launcher.
+pub fn call<B>(method: &str, body: &B) -> zbus::Result<()>
+where
+    B: serde::Serialize + zbus::zvariant::DynamicType,
+{
+    let conn = zbus::blocking::Connection::session()?;
+    conn.call_method(Some(APP_ID), OBJECT_PATH, Some(INTERFACE), method, body)?;
+    Ok(())
+}
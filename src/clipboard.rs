@@ -0,0 +1,62 @@
+//! Copies text and images to the Wayland clipboard.
+//!
+//! A selection is served by the client that set it, for as long as it owns
+//! it. The launcher surface is destroyed right after a copy and iced_sctk
+//! does not expose the data-device protocol, so the selection is handed to
+//! a background holder: a thread with a data control connection of its own,
+//! which keeps serving it until another client replaces it.
+
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::thread;
+
+use tokio::sync::oneshot;
+
+use crate::data_control;
+
+/// Types plain text is offered as, for clients that predate MIME types as
+/// well.
+pub const TEXT_TYPES: &[&str] = &[
+    "text/plain;charset=utf-8",
+    "text/plain",
+    "UTF8_STRING",
+    "STRING",
+    "TEXT",
+];
+
+/// Puts `text` on the clipboard.
+///
+/// Returns once the background holder owns the selection.
+pub async fn copy(text: String) -> io::Result<()> {
+    let data = Arc::new(text.into_bytes());
+    let contents = TEXT_TYPES
+        .iter()
+        .map(|mime| (mime.to_string(), data.clone()))
+        .collect();
+    hold(contents).await
+}
+
+/// Puts the contents of the file at `path` on the clipboard as `mime`.
+pub async fn copy_file(path: PathBuf, mime: String) -> io::Result<()> {
+    let data = tokio::fs::read(path).await?;
+    hold(vec![(mime, Arc::new(data))]).await
+}
+
+async fn hold(contents: Vec<(String, Arc<Vec<u8>>)>) -> io::Result<()> {
+    let (ready_tx, ready_rx) = oneshot::channel();
+    thread::Builder::new()
+        .name("clipboard".to_string())
+        .spawn(move || {
+            data_control::serve(contents, |res| {
+                let _ = ready_tx.send(res);
+            })
+        })?;
+    match ready_rx.await {
+        Ok(res) => res.map_err(|err| io::Error::new(io::ErrorKind::Other, format!("{err:#}"))),
+        Err(_) => Err(io::Error::new(
+            io::ErrorKind::Other,
+            "the clipboard holder stopped",
+        )),
+    }
+}
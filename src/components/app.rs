@@ -1,6 +1,8 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::ffi::OsStr;
-use std::fs;
-use std::process::exit;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 
 use cosmic::iced::alignment::{Horizontal, Vertical};
 use cosmic::iced::futures::{channel::mpsc, SinkExt};
@@ -12,11 +14,11 @@ use cosmic::iced_native::window::Id as SurfaceId;
 use cosmic::iced_style::{self, application};
 use cosmic::theme::{Button, Container, Svg};
 use cosmic::{settings, widget, Element, Theme};
-use freedesktop_desktop_entry::DesktopEntry;
-use iced::keyboard::KeyCode;
+use iced::keyboard::{KeyCode, Modifiers};
+use iced::mouse;
 use iced::wayland::Appearance;
-use iced::widget::{vertical_space, svg, Image};
-use iced::{Color, Alignment};
+use iced::widget::{svg, vertical_space, Image};
+use iced::{Alignment, Color};
 use iced_sctk::application::SurfaceIdWrapper;
 use iced_sctk::command::platform_specific::wayland::layer_surface::SctkLayerSurfaceSettings;
 use iced_sctk::commands;
@@ -27,11 +29,48 @@ use iced_sctk::settings::InitialSurface;
 use once_cell::sync::Lazy;
 use pop_launcher::{IconSource, SearchResult};
 
-use crate::config;
-use crate::subscriptions::launcher::{launcher, LauncherEvent, LauncherRequest};
+use crate::apps::{App, Apps};
+use crate::components::label::Label;
+use crate::components::pointer::Pointer;
+use crate::emoji::{self, Emoji, EmojiTable, SkinTone};
+use crate::localize::requested_languages;
+use crate::mime_apps::Associations;
+use crate::preferences::Preferences;
+use crate::preview::{self, FileDetails};
+use crate::providers::{self, Action};
+use crate::run::Completions;
+use crate::store::clipboard_history::{Clip, ClipboardHistory};
+use crate::store::favorites::{Favorite, Favorites};
+use crate::store::frecency::Frecency;
+use crate::store::query_history::QueryHistory;
+use crate::store::recent_emoji::RecentEmoji;
+use crate::subscriptions::clipboard::{clipboard_watcher, ClipboardEvent};
+use crate::subscriptions::launcher::{launcher, LauncherEvent, LauncherItem, LauncherRequest};
 use crate::subscriptions::toggle_dbus::{dbus_toggle, LauncherDbusEvent};
+use crate::subscriptions::toplevels::{toplevels, ToplevelEvent};
+use crate::toplevels::{Controller, Window};
+use crate::{cli, clipboard, config, fl, fuzzy, spawn};
 
 static INPUT_ID: Lazy<text_input::Id> = Lazy::new(text_input::Id::unique);
+/// Emoji per row of the emoji picker.
+const EMOJI_COLUMNS: usize = 8;
+/// Rows of the emoji picker shown at once, scrolled along with the selection.
+const EMOJI_ROWS: usize = 6;
+/// Applications per row of the application grid.
+const APP_COLUMNS: usize = 4;
+/// Rows of the application grid shown at once.
+const APP_ROWS: usize = 4;
+/// Category tabs per row above the application grid.
+const TAB_COLUMNS: usize = 6;
+/// Width of the input and the results.
+const RESULTS_WIDTH: u16 = 600;
+/// Width of the preview pane, added to the launcher when it is enabled.
+const PREVIEW_WIDTH: u16 = 320;
+/// Height of the images shown in the preview pane.
+const PREVIEW_IMAGE_HEIGHT: u16 = 200;
+/// Pixels scrolled by touchpads that count as one line of a mouse wheel.
+const SCROLL_LINE_PIXELS: f32 = 40.0;
+const NO_TERMINAL: &str = "no terminal found, set $TERMINAL";
 
 pub fn run() -> cosmic::iced::Result {
     let mut settings = settings();
@@ -46,6 +85,121 @@ pub fn run() -> cosmic::iced::Result {
     IcedLauncher::run(settings.into())
 }
 
+/// What the result list shows.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum Mode {
+    /// Results of the launcher service and the local providers.
+    #[default]
+    Search,
+    /// Submitted queries.
+    History,
+    /// Recent clipboard contents.
+    Clipboard,
+    /// Emoji, entered by starting the query with `:` by default.
+    Emoji,
+    /// Open windows, as listed by the compositor.
+    Windows,
+    /// A shell command, entered by starting the query with `>` by default.
+    Run,
+    /// Installed applications, browsed as a grid by category.
+    Apps,
+    /// The applications that can open a file, chosen from its options.
+    OpenWith,
+}
+
+impl Mode {
+    /// Parses the names accepted by `--mode` and as targets of prefixes.
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "history" => Some(Mode::History),
+            "clipboard" => Some(Mode::Clipboard),
+            "emoji" => Some(Mode::Emoji),
+            "windows" => Some(Mode::Windows),
+            "run" => Some(Mode::Run),
+            "apps" => Some(Mode::Apps),
+            _ => None,
+        }
+    }
+}
+
+/// An open window, along with the row it is shown as.
+#[derive(Debug, Clone)]
+struct WindowItem {
+    window: Window,
+    result: SearchResult,
+}
+
+impl WindowItem {
+    fn new(id: u32, window: Window) -> Self {
+        let description = if window.workspaces.is_empty() {
+            window.app_id.clone()
+        } else {
+            fl!(
+                "window-description",
+                app = window.app_id.as_str(),
+                workspace = window.workspaces.join(", ")
+            )
+        };
+        let result = SearchResult {
+            id,
+            name: window.title.clone(),
+            description,
+            icon: Some(IconSource::Name(Cow::Owned(window.app_id.clone()))),
+            category_icon: None,
+            window: None,
+        };
+        Self { window, result }
+    }
+}
+
+/// Options expanded below a result, such as the actions of its desktop
+/// entry.
+#[derive(Debug, Clone)]
+struct Context {
+    /// Row of the result the options belong to.
+    row: usize,
+    options: Vec<ContextOption>,
+    selected: usize,
+}
+
+#[derive(Debug, Clone)]
+enum ContextOption {
+    /// A `[Desktop Action]` of the desktop entry at `path`.
+    Action {
+        path: PathBuf,
+        id: String,
+        name: String,
+    },
+    /// An option of a local result.
+    Provider { name: String, action: Action },
+    /// Lists the applications that can open a file.
+    OpenWith(OpenWith),
+    /// Copies the text of the result.
+    Copy,
+}
+
+impl ContextOption {
+    fn name(&self) -> String {
+        match self {
+            ContextOption::Action { name, .. } | ContextOption::Provider { name, .. } => {
+                name.clone()
+            }
+            ContextOption::OpenWith(_) => fl!("open-with-chooser"),
+            ContextOption::Copy => fl!("copy"),
+        }
+    }
+}
+
+/// The file the open-with chooser lists the applications of.
+#[derive(Debug, Clone)]
+struct OpenWith {
+    uri: String,
+    name: String,
+    mime: String,
+    /// The query searched before, restored when leaving the chooser.
+    query: String,
+}
+
 #[derive(Default, Clone)]
 struct IcedLauncher {
     id_ctr: u64,
@@ -53,8 +207,59 @@ struct IcedLauncher {
     selected_item: Option<usize>,
     active_surface: Option<SurfaceId>,
     theme: Theme,
-    launcher_items: Vec<SearchResult>,
+    launcher_items: Vec<LauncherItem>,
+    /// Open windows for the window switcher, the one activated last first.
+    windows: Vec<WindowItem>,
+    /// Activates and closes windows, once the compositor shares them.
+    toplevels: Option<Controller>,
     tx: Option<mpsc::Sender<LauncherRequest>>,
+    preferences: Preferences,
+    frecency: Frecency,
+    favorites: Favorites,
+    /// Favorite to activate once the results of its query arrive.
+    pending_favorite: Option<Favorite>,
+    query_history: QueryHistory,
+    /// Age of the recalled query while browsing the history with Up/Down.
+    history_cursor: Option<usize>,
+    /// What was typed before browsing the history started.
+    history_draft: String,
+    /// What the result list shows, searched with the input.
+    mode: Mode,
+    /// The local provider searched alone, after its prefix was typed.
+    scope: Option<String>,
+    clipboard_history: ClipboardHistory,
+    emoji: EmojiTable,
+    recent_emoji: RecentEmoji,
+    completions: Completions,
+    apps: Apps,
+    /// Category tab of the application grid, all categories if unset.
+    category: Option<&'static str>,
+    /// Options of the selected result, while they are expanded.
+    context: Option<Context>,
+    /// The file of the open-with chooser.
+    open_with: Option<OpenWith>,
+    associations: Associations,
+    /// Details and thumbnails of the files of the results, by URI. `None`
+    /// while they are read, or if the file does not exist.
+    file_details: HashMap<String, Option<FileDetails>>,
+    /// Why the last command failed to start, shown above the results.
+    error: Option<String>,
+    modifiers: Modifiers,
+    /// Set while Alt or Super is held in the window switcher, which
+    /// activates the selected window once it is released.
+    cycling: bool,
+    /// Set while what was activated with the middle button is carried out,
+    /// or until the answer to the activation of a result takes it, which
+    /// then keeps the launcher open.
+    keep_open: bool,
+    /// Lines scrolled with the wheel that did not move the selection yet.
+    scrolled: f32,
+    /// Text typed into the focused window once the launcher is hidden.
+    pending_input: Option<String>,
+    /// When the surface was last dismissed while resuming is enabled.
+    hidden_at: Option<Instant>,
+    /// Set once a result was activated, until the surface is closed.
+    activated: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -62,7 +267,12 @@ enum Message {
     InputChanged(String),
     Activate(Option<usize>),
     Hide,
+    /// Hides the launcher once a result was activated, without keeping its
+    /// query to resume.
+    Activated,
     Select(Option<usize>),
+    SelectPrevious,
+    SelectNext,
     Clear,
     LauncherEvent(LauncherEvent),
     SentRequest,
@@ -70,6 +280,953 @@ enum Message {
     Layer(LayerEvent),
     Toggle,
     Closed,
+    ClearHistory,
+    ActivateFavorite(usize),
+    TogglePin,
+    MoveFavorite(isize),
+    Copy,
+    HistoryPrevious,
+    HistoryNext,
+    ToggleMode(Mode),
+    RecallHistory(usize),
+    ClearQueryHistory,
+    Clipboard(ClipboardEvent),
+    Toplevel(ToplevelEvent),
+    PasteClip(usize),
+    RemoveClip,
+    Open(String),
+    Modifiers(Modifiers),
+    SelectLeft,
+    SelectRight,
+    PickEmoji(String),
+    SkinTone(SkinTone),
+    Cycle(isize),
+    CloseWindow,
+    RunCommand(String),
+    CommandFailed(String),
+    LaunchApp(PathBuf, Option<String>),
+    Category(Option<&'static str>),
+    ToggleContext,
+    ActivateContext(usize),
+    Escape,
+    OpenWith(String),
+    /// Leaves the mode or provider entered with a prefix.
+    ExitMode,
+    FileDetails(String, Option<FileDetails>),
+    /// The applications, read again whenever a mode listing them is
+    /// entered. The ones read before are shown until then.
+    AppsLoaded(Apps),
+    AssociationsLoaded(Associations),
+    /// The commands and history of the shells, read again whenever the run
+    /// mode is entered.
+    CompletionsLoaded(Completions),
+    /// Expands the options of a row, as clicked with the secondary button.
+    ContextMenu(usize),
+    /// Handles a message, such as an activation, without hiding the
+    /// launcher afterwards.
+    KeepOpen(Box<Message>),
+    /// Lines scrolled with the wheel, positive upwards.
+    Scroll(f32),
+}
+
+impl IcedLauncher {
+    /// Favorites are only listed while nothing has been typed.
+    fn visible_favorites(&self) -> &[Favorite] {
+        if self.input_value.is_empty() && self.mode == Mode::Search {
+            self.favorites.entries()
+        } else {
+            &[]
+        }
+    }
+
+    /// Sends `request` to the launcher service, if it is running.
+    fn request(&self, request: LauncherRequest) -> Command<Message> {
+        match self.tx.as_ref() {
+            Some(tx) => {
+                let mut tx = tx.clone();
+                let cmd = async move { tx.send(request).await };
+                Command::perform(cmd, |res| match res {
+                    Ok(_) => Message::SentRequest,
+                    Err(err) => Message::Error(err.to_string()),
+                })
+            }
+            None => Command::none(),
+        }
+    }
+
+    /// Searches `query` with the launcher service and the local providers,
+    /// or with the provider of the current scope only.
+    fn search(&self, query: String) -> Command<Message> {
+        self.request(match self.scope.clone() {
+            Some(provider) => LauncherRequest::SearchIn(provider, query),
+            None => LauncherRequest::Search(query),
+        })
+    }
+
+    /// The name of the mode or provider the input is searched with, unless
+    /// it is the default search.
+    fn mode_label(&self) -> Option<String> {
+        if let Some(scope) = self.scope.as_deref() {
+            return Some(match scope {
+                "calculator" => fl!("mode-calculator"),
+                "web" => fl!("mode-web"),
+                "files" => fl!("mode-files"),
+                "recent" => fl!("mode-recent"),
+                "ssh" => fl!("mode-ssh"),
+                "scripts" => fl!("mode-scripts"),
+                other => other.to_string(),
+            });
+        }
+        match self.mode {
+            Mode::Search => None,
+            Mode::History => Some(fl!("mode-history")),
+            Mode::Clipboard => Some(fl!("mode-clipboard")),
+            Mode::Emoji => Some(fl!("mode-emoji")),
+            Mode::Windows => Some(fl!("mode-windows")),
+            Mode::Run => Some(fl!("mode-run")),
+            Mode::Apps => Some(fl!("mode-apps")),
+            Mode::OpenWith => Some(fl!("mode-open-with")),
+        }
+    }
+
+    /// Past queries matching the input, from the latest to the oldest.
+    fn history_matches(&self) -> Vec<(usize, &str)> {
+        self.query_history
+            .iter()
+            .filter(|(_, query)| fuzzy::is_match(&self.input_value, query))
+            .collect()
+    }
+
+    /// Clipboard contents matching the input, from the latest to the oldest.
+    /// Images are matched by their MIME type.
+    fn clipboard_matches(&self) -> Vec<(usize, &Clip)> {
+        self.clipboard_history
+            .iter()
+            .filter(|(_, clip)| match clip {
+                Clip::Text(text) => fuzzy::is_match(&self.input_value, text),
+                Clip::Image { mime, .. } => fuzzy::is_match(&self.input_value, mime),
+            })
+            .collect()
+    }
+
+    /// Emoji matching the input, or the recent ones followed by all others
+    /// while nothing has been typed.
+    fn emoji_matches(&self) -> Vec<&Emoji> {
+        if !self.input_value.trim().is_empty() {
+            return self.emoji.search(&self.input_value);
+        }
+        let mut matches: Vec<&Emoji> = self
+            .recent_emoji
+            .iter()
+            .filter_map(|glyph| self.emoji.get(glyph))
+            .collect();
+        matches.extend(
+            self.emoji
+                .iter()
+                .filter(|e| !self.recent_emoji.iter().any(|glyph| glyph == e.glyph)),
+        );
+        matches
+    }
+
+    /// Commands completing the input in the run mode.
+    fn run_matches(&self) -> Vec<&str> {
+        self.completions.matches(&self.input_value)
+    }
+
+    /// Open windows whose title or application matches the input.
+    fn window_matches(&self) -> Vec<&WindowItem> {
+        self.windows
+            .iter()
+            .filter(|item| {
+                fuzzy::is_match(&self.input_value, &item.window.title)
+                    || fuzzy::is_match(&self.input_value, &item.window.app_id)
+            })
+            .collect()
+    }
+
+    /// Activates the window at `row` of the window switcher.
+    fn activate_window(&mut self, row: usize) -> Command<Message> {
+        let window = self
+            .window_matches()
+            .get(row)
+            .map(|item| item.window.clone());
+        match (self.toplevels.as_ref(), window) {
+            (Some(toplevels), Some(window)) => {
+                toplevels.activate(&window);
+                self.update(Message::Activated)
+            }
+            _ => Command::none(),
+        }
+    }
+
+    /// Applications of the selected category matching the input.
+    fn app_matches(&self) -> Vec<&App> {
+        self.apps.matches(&self.input_value, self.category)
+    }
+
+    /// Applications of the open-with chooser matching the input, the
+    /// default one first.
+    fn handler_matches(&self) -> Vec<&App> {
+        match self.open_with.as_ref() {
+            Some(file) => self
+                .associations
+                .handlers(&self.apps, &file.mime)
+                .into_iter()
+                .filter(|app| fuzzy::is_match(&self.input_value, &app.name))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Columns of the modes shown as a grid, which Up and Down move across.
+    fn grid_columns(&self) -> Option<usize> {
+        match self.mode {
+            Mode::Emoji => Some(EMOJI_COLUMNS),
+            Mode::Apps => Some(APP_COLUMNS),
+            _ => None,
+        }
+    }
+
+    /// Reads again what the current mode lists from the system, as it may
+    /// have changed since the mode was last entered.
+    fn refresh_mode(&mut self) -> Command<Message> {
+        match self.mode {
+            Mode::Run => return Command::perform(load_completions(), Message::CompletionsLoaded),
+            Mode::Apps => {
+                self.category = None;
+                return Command::perform(load_apps(), Message::AppsLoaded);
+            }
+            Mode::OpenWith => {
+                return Command::batch(vec![
+                    Command::perform(load_apps(), Message::AppsLoaded),
+                    Command::perform(load_associations(), Message::AssociationsLoaded),
+                ]);
+            }
+            _ => {}
+        }
+        Command::none()
+    }
+
+    /// The application behind `row`, in the application grid or among the
+    /// results of the launcher service.
+    fn app_at(&self, row: usize) -> Option<&App> {
+        if self.mode == Mode::Apps {
+            return self.app_matches().get(row).copied();
+        }
+        let favorites = self.visible_favorites();
+        let result = if self.mode == Mode::Windows {
+            &self.window_matches().get(row).copied()?.result
+        } else {
+            match favorites.get(row) {
+                Some(favorite) => &favorite.result,
+                None => &self.launcher_items.get(row - favorites.len())?.result,
+            }
+        };
+        let icon = match result.icon.as_ref() {
+            Some(IconSource::Name(icon)) => Some(icon.as_ref()),
+            _ => None,
+        };
+        self.apps.find(&result.name, icon)
+    }
+
+    /// The options that can be expanded below `row`.
+    fn context_options(&self, row: usize) -> Vec<ContextOption> {
+        let mut options: Vec<ContextOption> = match self.app_at(row) {
+            Some(app) => app
+                .actions
+                .iter()
+                .map(|action| ContextOption::Action {
+                    path: app.path.clone(),
+                    id: action.id.clone(),
+                    name: action.name.clone(),
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+        if self.mode == Mode::Search {
+            let item = row
+                .checked_sub(self.visible_favorites().len())
+                .and_then(|i| self.launcher_items.get(i));
+            if let Some(item) = item {
+                options.extend(
+                    item.options
+                        .iter()
+                        .map(|(name, action)| ContextOption::Provider {
+                            name: name.clone(),
+                            action: action.clone(),
+                        }),
+                );
+                if let (Some(uri), Some(IconSource::Mime(mime))) =
+                    (item.uri.as_ref(), item.result.icon.as_ref())
+                {
+                    options.push(ContextOption::OpenWith(OpenWith {
+                        uri: uri.clone(),
+                        name: item.result.name.clone(),
+                        mime: mime.to_string(),
+                        query: self.input_value.clone(),
+                    }));
+                }
+            }
+        }
+        if matches!(self.mode, Mode::Search | Mode::Windows) {
+            options.push(ContextOption::Copy);
+        }
+        options
+    }
+
+    /// The result at the selected row, along with the item it belongs to
+    /// unless it is a favorite.
+    fn selected_result(&self) -> Option<(&SearchResult, Option<&LauncherItem>)> {
+        let row = self.selected_item.unwrap_or_default();
+        let favorites = match self.mode {
+            Mode::Search => self.visible_favorites(),
+            Mode::Windows => {
+                let item = self.window_matches().get(row).copied()?;
+                return Some((&item.result, None));
+            }
+            _ => return None,
+        };
+        match favorites.get(row) {
+            Some(favorite) => Some((&favorite.result, None)),
+            None => self
+                .launcher_items
+                .get(row - favorites.len())
+                .map(|item| (&item.result, Some(item))),
+        }
+    }
+
+    /// Reads the details and thumbnails of the files of new results, and
+    /// forgets those of the results that are gone.
+    fn load_file_details(&mut self) -> Command<Message> {
+        if !self.preferences.preview_pane && !self.preferences.thumbnails {
+            return Command::none();
+        }
+        let uris: Vec<String> = self
+            .launcher_items
+            .iter()
+            .filter_map(|item| item.uri.clone())
+            .collect();
+        self.file_details.retain(|uri, _| uris.contains(uri));
+        let mut cmds = Vec::new();
+        for uri in uris {
+            if self.file_details.contains_key(&uri) {
+                continue;
+            }
+            self.file_details.insert(uri.clone(), None);
+            cmds.push(Command::perform(
+                preview::load(uri.clone()),
+                move |details| Message::FileDetails(uri.clone(), details),
+            ));
+        }
+        Command::batch(cmds)
+    }
+
+    /// How long the last query is kept after hiding, if resuming is enabled.
+    fn resume_timeout(&self) -> Option<Duration> {
+        Some(self.preferences.resume_timeout)
+            .filter(|secs| *secs > 0)
+            .map(Duration::from_secs)
+    }
+
+    /// Forgets the query and selection, and searches the empty query again.
+    fn reset(&mut self) -> Command<Message> {
+        self.input_value.clear();
+        self.selected_item = None;
+        self.history_cursor = None;
+        self.mode = Mode::Search;
+        self.scope = None;
+        self.cycling = false;
+        self.keep_open = false;
+        self.error = None;
+        self.context = None;
+        self.open_with = None;
+        self.request(LauncherRequest::Search(String::new()))
+    }
+
+    /// Replaces the input with `query` and searches it.
+    fn fill(&mut self, query: String) -> Command<Message> {
+        self.input_value = query.clone();
+        self.selected_item = None;
+        Command::batch(vec![
+            self.search(query),
+            text_input::move_cursor_to_end(INPUT_ID.clone()),
+        ])
+    }
+
+    /// Carries out the activation of a local result, hiding the launcher
+    /// afterwards unless it was activated with the middle button.
+    fn perform(&mut self, action: Action) -> Command<Message> {
+        let cmd = self.perform_action(action);
+        if !std::mem::take(&mut self.keep_open) {
+            return cmd;
+        }
+        cmd.map(|message| match message {
+            Message::Activated => Message::SentRequest,
+            // the action a script answers with is carried out the same way
+            Message::LauncherEvent(LauncherEvent::Action(action)) => Message::KeepOpen(Box::new(
+                Message::LauncherEvent(LauncherEvent::Action(action)),
+            )),
+            message => message,
+        })
+    }
+
+    fn perform_action(&mut self, action: Action) -> Command<Message> {
+        match action {
+            Action::Fill(text) => self.fill(text),
+            Action::Copy(text) => Command::perform(clipboard::copy(text), |res| match res {
+                Ok(_) => Message::Activated,
+                Err(err) => Message::Error(format!("failed to copy to the clipboard: {err}")),
+            }),
+            Action::Open {
+                uri,
+                app: Some(app),
+            } => match spawn::open_with(&app, &[uri]) {
+                Some(cmd) => Command::perform(spawn::run(cmd), |res| match res {
+                    Ok(_) => Message::Activated,
+                    Err(err) => Message::CommandFailed(err),
+                }),
+                None => Command::none(),
+            },
+            Action::Open { uri, app: None } => {
+                Command::perform(spawn::open_uri(uri), |res| match res {
+                    Ok(_) => Message::Activated,
+                    Err(err) => Message::CommandFailed(err),
+                })
+            }
+            Action::Terminal(args) => match spawn::exec_in_terminal(&args) {
+                Some(cmd) => Command::perform(spawn::run(cmd), |res| match res {
+                    Ok(_) => Message::Activated,
+                    Err(err) => Message::CommandFailed(err),
+                }),
+                None => self.update(Message::CommandFailed(NO_TERMINAL.to_string())),
+            },
+            Action::Callback { script, id } => {
+                Command::perform(providers::scripts::activate(script, id), |res| match res {
+                    Ok(Some(action)) => Message::LauncherEvent(LauncherEvent::Action(action)),
+                    Ok(None) => Message::Activated,
+                    Err(err) => Message::CommandFailed(err),
+                })
+            }
+        }
+    }
+
+    /// Selects the row `offset` rows away from the current one.
+    fn move_selection(&mut self, offset: isize) {
+        let rows = match self.mode {
+            Mode::Search => self.visible_favorites().len() + self.launcher_items.len(),
+            Mode::Windows => self.window_matches().len(),
+            Mode::History => self.history_matches().len(),
+            Mode::Clipboard => self.clipboard_matches().len(),
+            Mode::Emoji => self.emoji_matches().len(),
+            Mode::Run => self.run_matches().len(),
+            Mode::Apps => self.app_matches().len(),
+            Mode::OpenWith => self.handler_matches().len(),
+        };
+        if rows == 0 {
+            self.selected_item = None;
+            return;
+        }
+        let selected = self.selected_item.unwrap_or_default() as isize + offset;
+        self.selected_item = Some(selected.clamp(0, rows as isize - 1) as usize);
+    }
+
+    /// A row of the result list: icons, name, description and the shortcut
+    /// that activates it. The `thumbnail` of a file replaces its icon.
+    fn result_row(
+        &self,
+        item: &SearchResult,
+        thumbnail: Option<&PathBuf>,
+        row: usize,
+        shortcut: Option<usize>,
+        on_press: Message,
+    ) -> Element<Message> {
+        let highlight = self.theme.extended_palette().primary.base.color;
+        let description_lines = if self.preferences.two_line_descriptions {
+            2
+        } else {
+            1
+        };
+
+        let name = Label::new(item.name.as_str())
+            .spans(fuzzy::match_spans(&self.input_value, &item.name))
+            .highlight(highlight);
+
+        let mut button_content = Vec::new();
+        if let Some(path) = item.category_icon.as_ref().and_then(|s| icon_path(s, 32)) {
+            if path.extension() == Some(&OsStr::new("svg")) {
+                button_content.push(
+                    svg::Svg::from_path(path)
+                        .width(Length::Units(24))
+                        .height(Length::Units(24))
+                        .style(Svg::Custom(|theme| iced_style::svg::Appearance {
+                            fill: Some(theme.palette().text),
+                        }))
+                        .into(),
+                )
+            } else {
+                button_content.push(
+                    Image::new(path)
+                        .width(Length::Units(24))
+                        .height(Length::Units(24))
+                        .into(),
+                )
+            }
+        }
+
+        let icon = thumbnail
+            .cloned()
+            .or_else(|| item.icon.as_ref().and_then(|s| icon_path(s, 24)));
+        if let Some(path) = icon {
+            if path.extension() == Some(&OsStr::new("svg")) {
+                button_content.push(
+                    svg::Svg::from_path(path)
+                        .width(Length::Units(24))
+                        .height(Length::Units(24))
+                        .into(),
+                )
+            } else {
+                button_content.push(
+                    Image::new(path)
+                        .width(Length::Units(24))
+                        .height(Length::Units(24))
+                        .into(),
+                )
+            }
+        }
+
+        let description = Label::new(item.description.as_str())
+            .spans(fuzzy::match_spans(&self.input_value, &item.description))
+            .highlight(highlight)
+            .max_lines(description_lines);
+
+        button_content.push(column![name, description].width(Length::Fill).into());
+        if let Some(i) = shortcut {
+            button_content.push(
+                container(
+                    text(format!("Ctrl + {i}"))
+                        .vertical_alignment(Vertical::Center)
+                        .horizontal_alignment(Horizontal::Right),
+                )
+                .width(Length::Shrink)
+                .center_y()
+                .align_y(Vertical::Center)
+                .align_x(Horizontal::Right)
+                .into(),
+            );
+        }
+
+        let btn = button(
+            helpers::row(button_content)
+                .spacing(8)
+                .align_items(Alignment::Center),
+        )
+        .width(Length::Fill)
+        .on_press(on_press.clone())
+        .padding([8, 16])
+        .style(Button::Text);
+
+        self.selectable(btn.into(), row, on_press)
+    }
+
+    /// A row of the query history search.
+    fn history_row<'a>(&self, query: &'a str, row: usize, age: usize) -> Element<'a, Message> {
+        let label = Label::new(query)
+            .spans(fuzzy::match_spans(&self.input_value, query))
+            .highlight(self.theme.extended_palette().primary.base.color);
+        let btn = button(label)
+            .width(Length::Fill)
+            .on_press(Message::RecallHistory(age))
+            .padding([8, 16])
+            .style(Button::Text);
+
+        self.selectable(btn.into(), row, Message::RecallHistory(age))
+    }
+
+    /// A row of the run mode. Nothing is selected until the selection is
+    /// moved, so that the input is run as typed.
+    fn command_row<'a>(&self, command: &'a str, row: usize) -> Element<'a, Message> {
+        let label = Label::new(command)
+            .spans(fuzzy::match_spans(&self.input_value, command))
+            .highlight(self.theme.extended_palette().primary.base.color);
+        let on_press = Message::RunCommand(command.to_string());
+        let btn = button(label)
+            .width(Length::Fill)
+            .on_press(on_press.clone())
+            .padding([8, 16])
+            .style(Button::Text);
+
+        let selected = self.selected_item == Some(row);
+        self.pointer(highlighted(btn.into(), selected), row, on_press)
+    }
+
+    /// A row of the open-with chooser, marking the default application.
+    fn handler_row<'a>(&self, app: &'a App, row: usize, default: bool) -> Element<'a, Message> {
+        let name = Label::new(app.name.as_str())
+            .spans(fuzzy::match_spans(&self.input_value, &app.name))
+            .highlight(self.theme.extended_palette().primary.base.color);
+        let mut content = vec![
+            app_icon(app.icon.as_deref(), 24),
+            container(name).width(Length::Fill).into(),
+        ];
+        if default {
+            content.push(text(fl!("open-with-default")).size(14).into());
+        }
+        let btn = button(
+            helpers::row(content)
+                .spacing(8)
+                .align_items(Alignment::Center),
+        )
+        .width(Length::Fill)
+        .on_press(Message::OpenWith(app.id.clone()))
+        .padding([8, 16])
+        .style(Button::Text);
+
+        self.selectable(btn.into(), row, Message::OpenWith(app.id.clone()))
+    }
+
+    /// A row of the clipboard history, previewing a few lines of text or
+    /// the image.
+    fn clip_row<'a>(&self, clip: &'a Clip, row: usize, age: usize) -> Element<'a, Message> {
+        let content: Element<Message> = match clip {
+            Clip::Text(text) => Label::new(text.as_str())
+                .spans(fuzzy::match_spans(&self.input_value, text))
+                .highlight(self.theme.extended_palette().primary.base.color)
+                .max_lines(3)
+                .into(),
+            Clip::Image { mime, path } => row![
+                Image::new(path).height(Length::Units(64)),
+                text(mime).size(14),
+            ]
+            .spacing(8)
+            .align_items(Alignment::Center)
+            .into(),
+        };
+        let btn = button(content)
+            .width(Length::Fill)
+            .on_press(Message::PasteClip(age))
+            .padding([8, 16])
+            .style(Button::Text);
+
+        self.selectable(btn.into(), row, Message::PasteClip(age))
+    }
+
+    /// The skin tones, the rows of emoji around the selected one and its name.
+    fn emoji_grid(&self) -> Vec<Element<Message>> {
+        let skin_tone = self.recent_emoji.skin_tone();
+        let sample = self.emoji.get("👋");
+        let skin_tones = SkinTone::ALL.iter().map(|tone| {
+            let glyph = sample.map(|e| e.with_skin_tone(*tone)).unwrap_or_default();
+            let btn = button(text(glyph).size(20))
+                .padding(4)
+                .on_press(Message::SkinTone(*tone))
+                .style(Button::Text);
+            highlighted(btn.into(), *tone == skin_tone)
+        });
+        let mut rows: Vec<Element<Message>> =
+            vec![helpers::row(skin_tones.collect()).spacing(4).into()];
+
+        let matches = self.emoji_matches();
+        let selected = self.selected_item.unwrap_or_default();
+        let first_row = (selected / EMOJI_COLUMNS).saturating_sub(EMOJI_ROWS - 1);
+        for (r, chunk) in matches
+            .chunks(EMOJI_COLUMNS)
+            .enumerate()
+            .skip(first_row)
+            .take(EMOJI_ROWS)
+        {
+            let cells = chunk.iter().enumerate().map(|(c, e)| {
+                let btn = button(
+                    text(e.with_skin_tone(skin_tone))
+                        .size(32)
+                        .horizontal_alignment(Horizontal::Center),
+                )
+                .width(Length::Units(64))
+                .padding(8)
+                .on_press(Message::PickEmoji(e.glyph.clone()))
+                .style(Button::Text);
+                highlighted(btn.into(), r * EMOJI_COLUMNS + c == selected)
+            });
+            rows.push(helpers::row(cells.collect()).spacing(4).into());
+        }
+
+        if let Some(e) = matches.get(selected) {
+            rows.push(text(&e.name).size(14).into());
+        }
+        rows
+    }
+
+    /// The category tabs and the rows of applications around the selected
+    /// one.
+    fn app_grid(&self) -> Vec<Element<Message>> {
+        let tabs: Vec<Option<&'static str>> = std::iter::once(None)
+            .chain(self.apps.categories().into_iter().map(Some))
+            .collect();
+        let mut rows: Vec<Element<Message>> = tabs
+            .chunks(TAB_COLUMNS)
+            .map(|chunk| {
+                let tabs = chunk.iter().map(|category| {
+                    let btn = button(text(category_name(*category)).size(14))
+                        .padding([4, 8])
+                        .on_press(Message::Category(*category))
+                        .style(Button::Text);
+                    highlighted(btn.into(), *category == self.category)
+                });
+                helpers::row(tabs.collect()).spacing(4).into()
+            })
+            .collect();
+
+        let matches = self.app_matches();
+        let selected = self.selected_item.unwrap_or_default();
+        let first_row = (selected / APP_COLUMNS).saturating_sub(APP_ROWS - 1);
+        for (r, chunk) in matches
+            .chunks(APP_COLUMNS)
+            .enumerate()
+            .skip(first_row)
+            .take(APP_ROWS)
+        {
+            let cells = chunk.iter().enumerate().map(|(c, app)| {
+                let btn = button(
+                    column![
+                        app_icon(app.icon.as_deref(), 48),
+                        text(&app.name)
+                            .size(12)
+                            .horizontal_alignment(Horizontal::Center),
+                    ]
+                    .spacing(4)
+                    .width(Length::Fill)
+                    .align_items(Alignment::Center),
+                )
+                .width(Length::Units(128))
+                .padding(8)
+                .on_press(Message::LaunchApp(app.path.clone(), None))
+                .style(Button::Text);
+                highlighted(btn.into(), r * APP_COLUMNS + c == selected)
+            });
+            rows.push(helpers::row(cells.collect()).spacing(4).into());
+        }
+        rows
+    }
+
+    /// The options expanded below `row`, if any.
+    fn context_rows(&self, row: usize) -> Vec<Element<Message>> {
+        let context = match self.context.as_ref() {
+            Some(context) if context.row == row => context,
+            _ => return Vec::new(),
+        };
+        context
+            .options
+            .iter()
+            .enumerate()
+            .map(|(i, option)| {
+                let btn = button(text(option.name()).size(14))
+                    .width(Length::Fill)
+                    .on_press(Message::ActivateContext(i))
+                    .padding([6, 16, 6, 56])
+                    .style(Button::Text);
+                highlighted(btn.into(), i == context.selected)
+            })
+            .collect()
+    }
+
+    /// The details of the selected result: its image or a larger icon, the
+    /// whole description, what its provider tells about it and the file it
+    /// stands for.
+    fn preview(&self) -> Element<Message> {
+        let mut content: Vec<Element<Message>> = Vec::new();
+        if let Some((result, item)) = self.selected_result() {
+            let file = item
+                .and_then(|item| item.uri.as_ref())
+                .and_then(|uri| self.file_details.get(uri)?.as_ref());
+            content.push(match file.and_then(|file| file.thumbnail.as_ref()) {
+                Some(path) => Image::new(path)
+                    .width(Length::Fill)
+                    .height(Length::Units(PREVIEW_IMAGE_HEIGHT))
+                    .into(),
+                None => sized_icon(result.icon.as_ref().and_then(|s| icon_path(s, 64)), 64),
+            });
+            content.push(text(&result.name).size(20).into());
+            if !result.description.is_empty() {
+                content.push(text(&result.description).size(14).into());
+            }
+
+            let mut details = item.map(|item| item.details.clone()).unwrap_or_default();
+            if let Some(file) = file {
+                details.push((fl!("preview-type"), file.mime.clone()));
+                if let Some(size) = file.size {
+                    details.push((fl!("preview-size"), preview::format_size(size)));
+                }
+                if let Some(modified) = file.modified {
+                    details.push((fl!("preview-modified"), preview::format_age(modified)));
+                }
+                details.push((
+                    fl!("preview-location"),
+                    file.path
+                        .parent()
+                        .map(providers::home_relative)
+                        .unwrap_or_default(),
+                ));
+            }
+            content.extend(details.into_iter().map(|(label, value)| {
+                column![text(label).size(12), text(value).size(14)]
+                    .spacing(2)
+                    .into()
+            }));
+        }
+        helpers::column(content)
+            .spacing(12)
+            .width(Length::Units(PREVIEW_WIDTH))
+            .into()
+    }
+
+    /// Width of the layer surface, which holds the preview pane if it is
+    /// enabled.
+    fn surface_width(&self) -> u32 {
+        let mut width = u32::from(RESULTS_WIDTH);
+        if self.preferences.preview_pane {
+            // along with the spacing between the results and the pane
+            width += u32::from(PREVIEW_WIDTH) + 24;
+        }
+        width
+    }
+
+    /// Wraps a row in a background that marks it when it is selected, and
+    /// lets the pointer select it.
+    fn selectable<'a>(
+        &self,
+        content: Element<'a, Message>,
+        row: usize,
+        on_press: Message,
+    ) -> Element<'a, Message> {
+        let content = highlighted(content, self.selected_item.unwrap_or_default() == row);
+        self.pointer(content, row, on_press)
+    }
+
+    /// Lets the pointer select the row by moving over it, expand its options
+    /// with the secondary button, and activate it with the middle button
+    /// while keeping the launcher open.
+    fn pointer<'a>(
+        &self,
+        content: Element<'a, Message>,
+        row: usize,
+        on_press: Message,
+    ) -> Element<'a, Message> {
+        Pointer::new(content)
+            .selected(self.selected_item == Some(row))
+            .on_hover(Message::Select(Some(row)))
+            .on_right_press(Message::ContextMenu(row))
+            .on_middle_press(Message::KeepOpen(Box::new(on_press)))
+            .into()
+    }
+}
+
+/// The icon file of `source`. MIME types are shown by the icon of the type,
+/// or by the generic icon of its media type such as `text-x-generic`.
+fn icon_path(source: &IconSource, size: u16) -> Option<PathBuf> {
+    let names = match source {
+        IconSource::Name(name) => vec![name.to_string()],
+        IconSource::Mime(mime) => {
+            let media = mime.split('/').next().unwrap_or_default();
+            vec![mime.replace('/', "-"), format!("{media}-x-generic")]
+        }
+    };
+    names.iter().find_map(|name| {
+        freedesktop_icons::lookup(name)
+            .with_theme("Pop")
+            .with_size(size)
+            .with_cache()
+            .find()
+    })
+}
+
+/// The installed applications, named in the user's language, read on a
+/// blocking thread since there may be hundreds of desktop entries.
+async fn load_apps() -> Apps {
+    let load = || {
+        let languages = requested_languages();
+        Apps::load(languages.first().map(|id| id.language.as_str()))
+    };
+    tokio::task::spawn_blocking(load).await.unwrap_or_default()
+}
+
+/// The commands on `$PATH` and the shell histories, read on a blocking
+/// thread since every file on `$PATH` is looked at.
+async fn load_completions() -> Completions {
+    tokio::task::spawn_blocking(Completions::load)
+        .await
+        .unwrap_or_default()
+}
+
+/// The default and added applications of the MIME types.
+async fn load_associations() -> Associations {
+    tokio::task::spawn_blocking(Associations::load)
+        .await
+        .unwrap_or_default()
+}
+
+/// The icon of an application, named or given as a path, at `size`.
+fn app_icon<'a>(icon: Option<&str>, size: u16) -> Element<'a, Message> {
+    let icon = icon.unwrap_or("application-x-executable");
+    let path = if Path::new(icon).is_absolute() {
+        Some(PathBuf::from(icon))
+    } else {
+        freedesktop_icons::lookup(icon)
+            .with_theme("Pop")
+            .with_size(size)
+            .with_cache()
+            .find()
+    };
+    sized_icon(path, size)
+}
+
+/// The icon file at `path` at `size`, or blank space if there is none.
+fn sized_icon<'a>(path: Option<PathBuf>, size: u16) -> Element<'a, Message> {
+    match path {
+        Some(path) if path.extension() == Some(OsStr::new("svg")) => svg::Svg::from_path(path)
+            .width(Length::Units(size))
+            .height(Length::Units(size))
+            .into(),
+        Some(path) => Image::new(path)
+            .width(Length::Units(size))
+            .height(Length::Units(size))
+            .into(),
+        None => vertical_space(Length::Units(size)).into(),
+    }
+}
+
+/// The label of a category tab.
+fn category_name(category: Option<&str>) -> String {
+    match category {
+        None => fl!("category-all"),
+        Some("AudioVideo") => fl!("category-audio-video"),
+        Some("Development") => fl!("category-development"),
+        Some("Education") => fl!("category-education"),
+        Some("Game") => fl!("category-game"),
+        Some("Graphics") => fl!("category-graphics"),
+        Some("Network") => fl!("category-network"),
+        Some("Office") => fl!("category-office"),
+        Some("Science") => fl!("category-science"),
+        Some("Settings") => fl!("category-settings"),
+        Some("System") => fl!("category-system"),
+        Some("Utility") => fl!("category-utility"),
+        Some(category) => category.to_string(),
+    }
+}
+
+/// Wraps `content` in a background that marks it if `highlighted` is set.
+fn highlighted<'a>(content: Element<'a, Message>, highlighted: bool) -> Element<'a, Message> {
+    container(content)
+        .style(if highlighted {
+            Container::Custom(|theme| container::Appearance {
+                text_color: None,
+                background: Some(theme.extended_palette().background.weak.color.into()),
+                border_radius: 8.0,
+                border_width: 0.0,
+                border_color: Color::TRANSPARENT,
+            })
+        } else {
+            Container::Custom(|_| container::Appearance::default())
+        })
+        .into()
 }
 
 impl Application for IcedLauncher {
@@ -79,9 +1236,28 @@ impl Application for IcedLauncher {
     type Flags = ();
 
     fn new(_flags: ()) -> (Self, Command<Message>) {
+        let mut preferences = Preferences::load();
+        preferences.check_prefixes(cli::MODES);
         (
-            IcedLauncher::default(),
-            commands::layer_surface::destroy_layer_surface(SurfaceId::new(0)),
+            IcedLauncher {
+                frecency: Frecency::load(),
+                favorites: Favorites::load(),
+                query_history: QueryHistory::load(),
+                emoji: EmojiTable::load(&requested_languages()),
+                recent_emoji: RecentEmoji::load(),
+                clipboard_history: ClipboardHistory::load(
+                    preferences.clipboard_history_size,
+                    preferences.clipboard_history_persist,
+                ),
+                preferences,
+                ..Default::default()
+            },
+            Command::batch(vec![
+                commands::layer_surface::destroy_layer_surface(SurfaceId::new(0)),
+                // the applications are also used in search, to tell the
+                // results of the launcher service apart
+                Command::perform(load_apps(), Message::AppsLoaded),
+            ]),
         )
     }
 
@@ -91,44 +1267,246 @@ impl Application for IcedLauncher {
 
     fn update(&mut self, message: Message) -> Command<Message> {
         match message {
+            Message::SelectPrevious if self.context.is_some() => {
+                if let Some(context) = self.context.as_mut() {
+                    context.selected = context.selected.saturating_sub(1);
+                }
+            }
+            Message::SelectNext if self.context.is_some() => {
+                if let Some(context) = self.context.as_mut() {
+                    context.selected = (context.selected + 1).min(context.options.len() - 1);
+                }
+            }
+            Message::Activate(None) if self.context.is_some() => {
+                let selected = self.context.as_ref().map_or(0, |context| context.selected);
+                return self.update(Message::ActivateContext(selected));
+            }
             Message::InputChanged(value) => {
+                // the input reports a change for Backspace even when it was
+                // already empty
+                let entered = self.mode != Mode::Search || self.scope.is_some();
+                if entered && value.is_empty() && self.input_value.is_empty() {
+                    return self.update(Message::ExitMode);
+                }
+                self.context = None;
                 self.input_value = value.clone();
-                if let Some(tx) = self.tx.as_ref() {
-                    let mut tx = tx.clone();
-                    let cmd = async move { tx.send(LauncherRequest::Search(value)).await };
-
-                    return Command::perform(cmd, |res| match res {
-                        Ok(_) => Message::SentRequest,
-                        Err(err) => Message::Error(err.to_string()),
-                    });
+                self.selected_item = None;
+                self.history_cursor = None;
+                self.pending_favorite = None;
+                self.error = None;
+                let mut value = value;
+                let mut cmd = Command::none();
+                if !entered {
+                    if let Some((target, rest)) = self.preferences.route(&value) {
+                        match Mode::from_name(target) {
+                            Some(mode) => {
+                                self.mode = mode;
+                                cmd = self.refresh_mode();
+                            }
+                            None => self.scope = Some(target.to_string()),
+                        }
+                        value = rest.to_string();
+                        self.input_value = value.clone();
+                        cmd = Command::batch(vec![
+                            cmd,
+                            text_input::move_cursor_to_end(INPUT_ID.clone()),
+                        ]);
+                    }
+                }
+                if self.mode != Mode::Search {
+                    return cmd;
                 }
+                return Command::batch(vec![cmd, self.search(value)]);
             }
+            Message::Activate(Some(i)) if self.mode == Mode::Windows => {
+                return self.activate_window(i);
+            }
+            // the other modes list rows of their own, the results of the
+            // last search behind them must not be activated by Ctrl+digit
+            Message::Activate(Some(_)) if self.mode != Mode::Search => {}
             Message::Activate(Some(i)) => {
+                self.query_history.push(&self.input_value);
                 if let (Some(tx), Some(item)) = (self.tx.as_ref(), self.launcher_items.get(i)) {
+                    if self.preferences.frecency {
+                        self.frecency.record(&item.result, &self.input_value);
+                    }
                     let mut tx = tx.clone();
-                    let id = item.id;
-                    let cmd = async move { tx.send(LauncherRequest::Activate(id)).await };
+                    let (origin, id) = (item.origin, item.result.id);
+                    let cmd = async move { tx.send(LauncherRequest::Activate(origin, id)).await };
                     return Command::batch(vec![Command::perform(cmd, |res| match res {
-                        Ok(_) => Message::Hide,
+                        Ok(_) => Message::Activated,
                         Err(err) => Message::Error(err.to_string()),
                     })]);
                 }
             }
             Message::Activate(None) => {
+                let selected = self.selected_item.unwrap_or_default();
+                match self.mode {
+                    Mode::Search => {}
+                    Mode::History => {
+                        return match self.history_matches().get(selected) {
+                            Some((age, _)) => self.update(Message::RecallHistory(*age)),
+                            None => Command::none(),
+                        };
+                    }
+                    Mode::Clipboard => {
+                        return match self.clipboard_matches().get(selected) {
+                            Some((age, _)) => self.update(Message::PasteClip(*age)),
+                            None => Command::none(),
+                        };
+                    }
+                    Mode::Emoji => {
+                        return match self.emoji_matches().get(selected) {
+                            Some(e) => self.update(Message::PickEmoji(e.glyph.clone())),
+                            None => Command::none(),
+                        };
+                    }
+                    Mode::Apps => {
+                        let path = self.app_matches().get(selected).map(|app| app.path.clone());
+                        return match path {
+                            Some(path) => self.update(Message::LaunchApp(path, None)),
+                            None => Command::none(),
+                        };
+                    }
+                    Mode::OpenWith => {
+                        let id = self
+                            .handler_matches()
+                            .get(selected)
+                            .map(|app| app.id.clone());
+                        return match id {
+                            Some(id) => self.update(Message::OpenWith(id)),
+                            None => Command::none(),
+                        };
+                    }
+                    Mode::Run => {
+                        let command = match self.selected_item {
+                            Some(row) => self.run_matches().get(row).map(|c| c.to_string()),
+                            None => Some(self.input_value.clone()),
+                        };
+                        return match command.filter(|c| !c.trim().is_empty()) {
+                            Some(command) => self.update(Message::RunCommand(command)),
+                            None => Command::none(),
+                        };
+                    }
+                    Mode::Windows => return self.activate_window(selected),
+                }
+                self.query_history.push(&self.input_value);
+                let favorites = self.visible_favorites().len();
+                if selected < favorites {
+                    return self.update(Message::ActivateFavorite(selected));
+                }
                 if let (Some(tx), Some(item)) = (
                     self.tx.as_ref(),
-                    self.launcher_items
-                        .get(self.selected_item.unwrap_or_default()),
+                    self.launcher_items.get(selected - favorites),
                 ) {
+                    if self.preferences.frecency {
+                        self.frecency.record(&item.result, &self.input_value);
+                    }
                     let mut tx = tx.clone();
-                    let id = item.id;
-                    let cmd = async move { tx.send(LauncherRequest::Activate(id)).await };
+                    let (origin, id) = (item.origin, item.result.id);
+                    let cmd = async move { tx.send(LauncherRequest::Activate(origin, id)).await };
                     return Command::perform(cmd, |res| match res {
                         Ok(_) => Message::SentRequest,
                         Err(err) => Message::Error(err.to_string()),
                     });
                 }
             }
+            Message::ActivateFavorite(i) => {
+                if let Some(favorite) = self.favorites.get(i).cloned() {
+                    let query = favorite.query.clone();
+                    self.pending_favorite = Some(favorite);
+                    return self.fill(query);
+                }
+            }
+            // windows and the rows of the other modes are not pinned
+            Message::TogglePin if self.mode != Mode::Search => {}
+            Message::TogglePin => {
+                let selected = self.selected_item.unwrap_or_default();
+                let favorites = self.visible_favorites().len();
+                if selected < favorites {
+                    self.favorites.remove(selected);
+                } else if let Some(item) = self.launcher_items.get(selected - favorites) {
+                    self.favorites.toggle(&item.result, &self.input_value);
+                }
+                self.move_selection(0);
+            }
+            Message::Copy => {
+                // like Enter, Ctrl+Shift+C applies to the first row until another one is selected
+                let selected = self.selected_item.unwrap_or_default();
+                match self.mode {
+                    Mode::Search => {}
+                    Mode::Windows => {
+                        let title = self
+                            .window_matches()
+                            .get(selected)
+                            .map(|item| item.window.title.clone());
+                        return match title {
+                            Some(title) => self.perform(Action::Copy(title)),
+                            None => Command::none(),
+                        };
+                    }
+                    Mode::History => {
+                        let query = self
+                            .history_matches()
+                            .get(selected)
+                            .map(|(_, q)| q.to_string());
+                        return match query {
+                            Some(query) => self.perform(Action::Copy(query)),
+                            None => Command::none(),
+                        };
+                    }
+                    Mode::Clipboard => {
+                        return match self.clipboard_matches().get(selected) {
+                            Some((age, _)) => self.update(Message::PasteClip(*age)),
+                            None => Command::none(),
+                        };
+                    }
+                    Mode::Emoji => {
+                        return match self.emoji_matches().get(selected) {
+                            Some(e) => self.update(Message::PickEmoji(e.glyph.clone())),
+                            None => Command::none(),
+                        };
+                    }
+                    Mode::Run => {
+                        let command = self.run_matches().get(selected).map(|c| c.to_string());
+                        return match command {
+                            Some(command) => self.perform(Action::Copy(command)),
+                            None => Command::none(),
+                        };
+                    }
+                    Mode::OpenWith => {
+                        let name = self
+                            .handler_matches()
+                            .get(selected)
+                            .map(|app| app.name.clone());
+                        return match name {
+                            Some(name) => self.perform(Action::Copy(name)),
+                            None => Command::none(),
+                        };
+                    }
+                    Mode::Apps => {
+                        let name = self.app_matches().get(selected).map(|app| app.name.clone());
+                        return match name {
+                            Some(name) => self.perform(Action::Copy(name)),
+                            None => Command::none(),
+                        };
+                    }
+                }
+                let favorites = self.visible_favorites();
+                if let Some(favorite) = favorites.get(selected) {
+                    let name = favorite.result.name.clone();
+                    return self.perform(Action::Copy(name));
+                }
+                if let Some(item) = self.launcher_items.get(selected - favorites.len()) {
+                    return self.request(LauncherRequest::Copy(item.origin, item.result.id));
+                }
+            }
+            Message::MoveFavorite(offset) => {
+                let selected = self.selected_item.unwrap_or_default();
+                if selected < self.visible_favorites().len() {
+                    self.selected_item = Some(self.favorites.move_by(selected, offset));
+                }
+            }
             Message::LauncherEvent(e) => match e {
                 LauncherEvent::Started(tx) => {
                     let mut tx_clone = tx.clone();
@@ -142,8 +1520,12 @@ impl Application for IcedLauncher {
                     });
                 }
                 LauncherEvent::Response(response) => match response {
+                    // the service launched the result itself, while the
+                    // launcher keeps running to be opened again
                     pop_launcher::Response::Close => {
-                        exit(0);
+                        if !std::mem::take(&mut self.keep_open) {
+                            return self.update(Message::Activated);
+                        }
                     }
                     pop_launcher::Response::Context { id, options } => {
                         // TODO ASHLEY
@@ -152,36 +1534,66 @@ impl Application for IcedLauncher {
                         path,
                         gpu_preference,
                     } => {
-                        if let Ok(bytes) = fs::read_to_string(&path) {
-                            if let Ok(entry) = DesktopEntry::decode(&path, &bytes) {
-                                let mut exec = match entry.exec() {
-                                    Some(exec_str) => shlex::Shlex::new(exec_str),
-                                    None => return Command::none(),
-                                };
-                                let mut cmd = match exec.next() {
-                                    Some(cmd) => tokio::process::Command::new(cmd),
-                                    None => return Command::none(),
-                                };
-                                for arg in exec {
-                                    cmd.arg(arg);
-                                }
-                                let _ = cmd.spawn();
-                                return Command::perform(async {}, |_| Message::Hide);
-                            }
+                        if let Some(cmd) = spawn::desktop_entry(&path, None) {
+                            let keep_open = std::mem::take(&mut self.keep_open);
+                            return Command::perform(spawn::run(cmd), move |res| match res {
+                                Ok(_) if keep_open => Message::SentRequest,
+                                Ok(_) => Message::Activated,
+                                Err(err) => Message::CommandFailed(err),
+                            });
                         }
                     }
-                    pop_launcher::Response::Update(list) => {
-                        self.launcher_items.splice(.., list);
-                    }
+                    // merged with the local results and sent as `LauncherEvent::Update`
+                    pop_launcher::Response::Update(_) => {}
                     pop_launcher::Response::Fill(s) => {
                         self.input_value = s;
                     }
                 },
+                LauncherEvent::Update {
+                    query,
+                    items: mut list,
+                    answered,
+                } => {
+                    if self.preferences.frecency {
+                        self.frecency
+                            .rank(&self.input_value, &mut list, |item| &item.result);
+                    }
+                    self.launcher_items.splice(.., list);
+                    let load = self.load_file_details();
+                    // the rows the options belonged to may have moved
+                    self.context = None;
+                    match self.pending_favorite.take() {
+                        // the favorite may be a result of the service, so
+                        // its answer is waited for
+                        Some(favorite) if answered && favorite.query == query => match self
+                            .launcher_items
+                            .iter()
+                            .find(|item| favorite.matches(&item.result))
+                        {
+                            Some(item) => {
+                                let (origin, id) = (item.origin, item.result.id);
+                                if self.preferences.frecency {
+                                    self.frecency.record(&favorite.result, &favorite.query);
+                                }
+                                return self.request(LauncherRequest::Activate(origin, id));
+                            }
+                            None => log::warn!(
+                                "favorite '{}' is no longer available",
+                                favorite.result.name
+                            ),
+                        },
+                        pending => self.pending_favorite = pending,
+                    }
+                    return load;
+                }
+                LauncherEvent::Action(action) => return self.perform(action),
                 LauncherEvent::Error(err) => {
                     log::error!("{}", err);
                 }
             },
             Message::Clear => {
+                self.mode = Mode::Search;
+                self.scope = None;
                 self.input_value.clear();
                 if let Some(tx) = self.tx.as_ref() {
                     let mut tx = tx.clone();
@@ -192,13 +1604,429 @@ impl Application for IcedLauncher {
                     });
                 }
             }
+            Message::ClearHistory => {
+                self.frecency.clear();
+            }
             Message::SentRequest => {}
             Message::Error(err) => {
                 log::error!("{}", err);
             }
             Message::Select(i) => {
+                // the options belong to the row that was selected
+                if i != self.selected_item {
+                    self.context = None;
+                }
                 self.selected_item = i;
             }
+            Message::ContextMenu(row) => {
+                self.selected_item = Some(row);
+                self.context = None;
+                return self.update(Message::ToggleContext);
+            }
+            Message::KeepOpen(message) => {
+                // the results of the last search are carried out once the
+                // service or their provider answers, which takes the flag
+                let answered_later = self.mode == Mode::Search
+                    && self.context.is_none()
+                    && matches!(
+                        *message,
+                        Message::Activate(_) | Message::ActivateFavorite(_)
+                    );
+                self.keep_open = true;
+                let cmd = self.update(*message);
+                // the other messages read it while they are handled
+                if !answered_later {
+                    self.keep_open = false;
+                }
+                return cmd.map(|message| match message {
+                    Message::Activated => Message::SentRequest,
+                    message => message,
+                });
+            }
+            Message::Scroll(lines) => {
+                self.scrolled += lines;
+                let steps = self.scrolled.trunc();
+                self.scrolled -= steps;
+                // scrolling up moves the selection up
+                let offset = -(steps as isize);
+                if offset == 0 {
+                    return Command::none();
+                }
+                match self.context.as_mut() {
+                    Some(context) => {
+                        let selected = context.selected as isize + offset;
+                        context.selected =
+                            selected.clamp(0, context.options.len() as isize - 1) as usize;
+                    }
+                    None => self.move_selection(offset),
+                }
+            }
+            Message::SelectPrevious => {
+                if self.mode == Mode::Run && self.selected_item == Some(0) {
+                    // back to running the input as typed
+                    self.selected_item = None;
+                    return Command::none();
+                }
+                if let Some(columns) = self.grid_columns() {
+                    self.move_selection(-(columns as isize));
+                    return Command::none();
+                }
+                if self.mode == Mode::Search
+                    && self.selected_item.unwrap_or_default() == 0
+                    && (self.input_value.is_empty() || self.history_cursor.is_some())
+                {
+                    return self.update(Message::HistoryPrevious);
+                }
+                self.move_selection(-1);
+            }
+            Message::SelectNext => {
+                // the results of a recalled query come before newer queries
+                if self.mode == Mode::Search && self.history_cursor.is_some() {
+                    let rows = self.visible_favorites().len() + self.launcher_items.len();
+                    if self.selected_item.unwrap_or_default() + 1 >= rows {
+                        return self.update(Message::HistoryNext);
+                    }
+                }
+                if let Some(columns) = self.grid_columns() {
+                    self.move_selection(columns as isize);
+                    return Command::none();
+                }
+                if self.mode == Mode::Run && self.selected_item.is_none() {
+                    self.move_selection(0);
+                    return Command::none();
+                }
+                self.move_selection(1);
+            }
+            Message::SelectLeft => {
+                if self.grid_columns().is_some() {
+                    self.move_selection(-1);
+                }
+            }
+            Message::SelectRight => {
+                if self.grid_columns().is_some() {
+                    self.move_selection(1);
+                }
+            }
+            Message::Modifiers(modifiers) => {
+                self.modifiers = modifiers;
+                if self.mode == Mode::Windows && self.active_surface.is_some() {
+                    if modifiers.alt() || modifiers.logo() {
+                        self.cycling = true;
+                    } else if std::mem::take(&mut self.cycling) {
+                        return self.update(Message::Activate(None));
+                    }
+                }
+            }
+            Message::Cycle(_) if self.mode == Mode::Run => {
+                // Tab completes the input with the selected command
+                let selected = self.selected_item.unwrap_or_default();
+                let command = self.run_matches().get(selected).map(|c| c.to_string());
+                if let Some(command) = command {
+                    self.input_value = command;
+                    self.selected_item = None;
+                    return text_input::move_cursor_to_end(INPUT_ID.clone());
+                }
+            }
+            Message::Cycle(_) if self.mode == Mode::Search => {
+                // Tab expands the options of the selected result
+                return self.update(Message::ToggleContext);
+            }
+            Message::Cycle(offset) if self.mode == Mode::Apps => {
+                // Tab switches between the category tabs
+                let mut tabs = vec![None];
+                tabs.extend(self.apps.categories().into_iter().map(Some));
+                let current = tabs.iter().position(|c| *c == self.category);
+                let next =
+                    (current.unwrap_or_default() as isize + offset).rem_euclid(tabs.len() as isize);
+                return self.update(Message::Category(tabs[next as usize]));
+            }
+            Message::Cycle(offset) => {
+                let rows = self.window_matches().len() as isize;
+                if self.mode == Mode::Windows && rows > 0 {
+                    let selected = self.selected_item.unwrap_or_default() as isize + offset;
+                    self.selected_item = Some(selected.rem_euclid(rows) as usize);
+                }
+            }
+            Message::RunCommand(command) => {
+                let cmd = if self.modifiers.shift() {
+                    match spawn::in_terminal(&command) {
+                        Some(cmd) => cmd,
+                        None => {
+                            return self.update(Message::CommandFailed(NO_TERMINAL.to_string()))
+                        }
+                    }
+                } else {
+                    spawn::shell(&command)
+                };
+                return Command::perform(spawn::run(cmd), |res| match res {
+                    Ok(_) => Message::Activated,
+                    Err(err) => Message::CommandFailed(err),
+                });
+            }
+            Message::LaunchApp(path, action) => {
+                if let Some(cmd) = spawn::desktop_entry(&path, action.as_deref()) {
+                    return Command::perform(spawn::run(cmd), |res| match res {
+                        Ok(_) => Message::Activated,
+                        Err(err) => Message::CommandFailed(err),
+                    });
+                }
+            }
+            Message::Category(category) => {
+                self.category = category;
+                self.selected_item = None;
+                self.context = None;
+            }
+            Message::ToggleContext => {
+                if self.context.take().is_some() {
+                    return Command::none();
+                }
+                let row = self.selected_item.unwrap_or_default();
+                let options = self.context_options(row);
+                if !options.is_empty() {
+                    self.context = Some(Context {
+                        row,
+                        options,
+                        selected: 0,
+                    });
+                }
+            }
+            Message::ActivateContext(i) => {
+                let (row, option) = match self.context.take() {
+                    Some(context) => (context.row, context.options.into_iter().nth(i)),
+                    None => return Command::none(),
+                };
+                match option {
+                    Some(ContextOption::Action { path, id, .. }) => {
+                        return self.update(Message::LaunchApp(path, Some(id)));
+                    }
+                    Some(ContextOption::Provider { action, .. }) => return self.perform(action),
+                    Some(ContextOption::OpenWith(file)) => {
+                        self.open_with = Some(file);
+                        self.mode = Mode::OpenWith;
+                        self.input_value.clear();
+                        self.selected_item = None;
+                        return self.refresh_mode();
+                    }
+                    Some(ContextOption::Copy) => {
+                        self.selected_item = Some(row);
+                        return self.update(Message::Copy);
+                    }
+                    None => {}
+                }
+            }
+            Message::Escape => {
+                if self.context.take().is_some() {
+                    return Command::none();
+                }
+                if self.mode == Mode::OpenWith {
+                    // back to the results the file was chosen from
+                    self.mode = Mode::Search;
+                    let query = self.open_with.take().map(|file| file.query);
+                    return self.fill(query.unwrap_or_default());
+                }
+                return self.update(Message::Hide);
+            }
+            Message::ExitMode => {
+                if self.mode == Mode::OpenWith {
+                    self.context = None;
+                    return self.update(Message::Escape);
+                }
+                self.mode = Mode::Search;
+                self.scope = None;
+                self.selected_item = None;
+                self.cycling = false;
+                self.context = None;
+                return Command::batch(vec![
+                    self.search(self.input_value.clone()),
+                    text_input::focus(INPUT_ID.clone()),
+                ]);
+            }
+            Message::FileDetails(uri, details) => {
+                // the results may have changed while the file was read
+                if let Some(entry) = self.file_details.get_mut(&uri) {
+                    *entry = details;
+                }
+            }
+            Message::AppsLoaded(apps) => {
+                self.apps = apps;
+                // the tab may have lost its last application
+                if !self
+                    .category
+                    .map_or(true, |c| self.apps.categories().contains(&c))
+                {
+                    self.category = None;
+                }
+            }
+            Message::AssociationsLoaded(associations) => self.associations = associations,
+            Message::CompletionsLoaded(completions) => self.completions = completions,
+            Message::OpenWith(id) => {
+                let (file, path) = match (self.open_with.as_ref(), self.apps.get(&id)) {
+                    (Some(file), Some(app)) => (file.clone(), app.path.clone()),
+                    _ => return Command::none(),
+                };
+                // Ctrl+Enter also makes the application the default
+                if self.modifiers.control() {
+                    if let Err(err) = self.associations.set_default(&file.mime, &id) {
+                        log::warn!("failed to set the default application: {err}");
+                        self.error = Some(err.to_string());
+                        return Command::none();
+                    }
+                }
+                return self.perform(Action::Open {
+                    uri: file.uri,
+                    app: Some(path),
+                });
+            }
+            Message::CommandFailed(err) => {
+                log::warn!("failed to run a command: {err}");
+                self.error = Some(err);
+            }
+            Message::CloseWindow => {
+                let selected = self.selected_item.unwrap_or_default();
+                if self.mode == Mode::Windows {
+                    // the window may ask first, so it stays listed until
+                    // the compositor reports it closed
+                    if let (Some(toplevels), Some(item)) =
+                        (self.toplevels.as_ref(), self.window_matches().get(selected))
+                    {
+                        toplevels.close(&item.window);
+                    }
+                }
+            }
+            Message::PickEmoji(glyph) => {
+                let text = match self.emoji.get(&glyph) {
+                    Some(e) => e.with_skin_tone(self.recent_emoji.skin_tone()),
+                    None => return Command::none(),
+                };
+                self.recent_emoji.push(&glyph);
+                if self.modifiers.shift() {
+                    // typed once the focus is back on the previous window
+                    self.pending_input = Some(text);
+                    return self.update(Message::Activated);
+                }
+                return self.perform(Action::Copy(text));
+            }
+            Message::SkinTone(skin_tone) => {
+                self.recent_emoji.set_skin_tone(skin_tone);
+            }
+            Message::Open(name) => {
+                let mode = match Mode::from_name(&name) {
+                    Some(mode) => mode,
+                    None => {
+                        log::warn!("unknown mode '{}'", name);
+                        return Command::none();
+                    }
+                };
+                let toggle = match self.active_surface {
+                    Some(_) => Command::none(),
+                    None => self.update(Message::Toggle),
+                };
+                self.mode = mode;
+                self.input_value.clear();
+                self.selected_item = None;
+                self.error = None;
+                self.context = None;
+                let cmd = Command::batch(vec![toggle, self.refresh_mode()]);
+                if mode == Mode::Windows {
+                    // like Alt+Tab, the window used before the current one
+                    // is selected first
+                    self.selected_item = Some(1);
+                    self.move_selection(0);
+                }
+                return cmd;
+            }
+            Message::HistoryPrevious => {
+                let age = self.history_cursor.map_or(0, |age| age + 1);
+                if let Some(query) = self.query_history.get(age).map(str::to_string) {
+                    if self.history_cursor.is_none() {
+                        self.history_draft = self.input_value.clone();
+                    }
+                    self.history_cursor = Some(age);
+                    return self.fill(query);
+                }
+            }
+            Message::HistoryNext => match self.history_cursor {
+                Some(0) => {
+                    self.history_cursor = None;
+                    let draft = std::mem::take(&mut self.history_draft);
+                    return self.fill(draft);
+                }
+                Some(age) => {
+                    if let Some(query) = self.query_history.get(age - 1).map(str::to_string) {
+                        self.history_cursor = Some(age - 1);
+                        return self.fill(query);
+                    }
+                }
+                None => {}
+            },
+            Message::ToggleMode(mode) => {
+                self.mode = if self.mode == mode {
+                    Mode::Search
+                } else {
+                    mode
+                };
+                self.scope = None;
+                self.selected_item = None;
+                self.cycling = false;
+                self.context = None;
+                let cmd = self.refresh_mode();
+                if self.mode == Mode::Search {
+                    return self.request(LauncherRequest::Search(self.input_value.clone()));
+                }
+                return cmd;
+            }
+            Message::RecallHistory(age) => {
+                if let Some(query) = self.query_history.get(age).map(str::to_string) {
+                    self.mode = Mode::Search;
+                    self.scope = None;
+                    return self.fill(query);
+                }
+            }
+            Message::ClearQueryHistory => {
+                self.query_history.clear();
+                self.history_cursor = None;
+            }
+            Message::Toplevel(event) => match event {
+                ToplevelEvent::Started(toplevels) => self.toplevels = Some(toplevels),
+                ToplevelEvent::Update(windows) => {
+                    self.windows = windows
+                        .into_iter()
+                        .zip(0..)
+                        .map(|(window, id)| WindowItem::new(id, window))
+                        .collect();
+                    if self.mode == Mode::Windows {
+                        self.move_selection(0);
+                    }
+                }
+            },
+            Message::Clipboard(event) => match event {
+                ClipboardEvent::Text(text) => self.clipboard_history.push_text(text),
+                ClipboardEvent::Image { mime, data } => {
+                    self.clipboard_history.push_image(mime, &data)
+                }
+            },
+            Message::PasteClip(age) => match self.clipboard_history.get(age).cloned() {
+                Some(Clip::Text(text)) => return self.perform(Action::Copy(text)),
+                Some(Clip::Image { mime, path }) => {
+                    return Command::perform(clipboard::copy_file(path, mime), |res| match res {
+                        Ok(_) => Message::Activated,
+                        Err(err) => {
+                            Message::Error(format!("failed to copy to the clipboard: {err}"))
+                        }
+                    });
+                }
+                None => {}
+            },
+            Message::RemoveClip => {
+                if self.mode == Mode::Clipboard {
+                    let selected = self.selected_item.unwrap_or_default();
+                    let age = self.clipboard_matches().get(selected).map(|(age, _)| *age);
+                    if let Some(age) = age {
+                        self.clipboard_history.remove(age);
+                        self.move_selection(0);
+                    }
+                }
+            }
             Message::Layer(e) => match e {
                 LayerEvent::Focused(_) => {
                     return text_input::focus(INPUT_ID.clone());
@@ -212,46 +2040,54 @@ impl Application for IcedLauncher {
             },
             Message::Closed => {
                 self.active_surface.take();
-                let mut cmds = Vec::new();
-                if let Some(tx) = self.tx.as_ref() {
-                    let mut tx = tx.clone();
-                    let search_cmd =
-                        async move { tx.send(LauncherRequest::Search("".to_string())).await };
-                    cmds.push(Command::perform(search_cmd, |res| match res {
+                self.cycling = false;
+                let typed = match self.pending_input.take() {
+                    Some(text) => Command::perform(emoji::type_text(text), |res| match res {
                         Ok(_) => Message::SentRequest,
-                        Err(err) => Message::Error(err.to_string()),
-                    }));
+                        Err(err) => Message::Error(format!("failed to type the emoji: {err}")),
+                    }),
+                    None => Command::none(),
+                };
+                // a task that was carried out is not resumed
+                let activated = std::mem::take(&mut self.activated);
+                if self.resume_timeout().is_some() && !activated {
+                    // keep the query and results around in case the launcher is reopened soon
+                    self.hidden_at = Some(Instant::now());
+                    return typed;
                 }
-                self.input_value = "".to_string();
-                cmds.push(text_input::focus(INPUT_ID.clone()));
-                return Command::batch(cmds);
+                return Command::batch(vec![
+                    typed,
+                    self.reset(),
+                    text_input::focus(INPUT_ID.clone()),
+                ]);
             }
             Message::Toggle => {
                 if let Some(id) = self.active_surface {
                     return commands::layer_surface::destroy_layer_surface(id);
                 } else {
                     self.id_ctr += 1;
+                    let resume = match (self.hidden_at.take(), self.resume_timeout()) {
+                        (Some(hidden_at), Some(timeout)) => hidden_at.elapsed() < timeout,
+                        _ => false,
+                    };
                     let mut cmds = Vec::new();
-                    if let Some(tx) = self.tx.as_ref() {
-                        let mut tx = tx.clone();
-                        let search_cmd =
-                            async move { tx.send(LauncherRequest::Search("".to_string())).await };
-                        cmds.push(Command::perform(search_cmd, |res| match res {
-                            Ok(_) => Message::SentRequest,
-                            Err(err) => Message::Error(err.to_string()),
-                        }));
-                    }
-                    self.input_value = "".to_string();
+                    if !resume {
+                        cmds.push(self.reset());
+                    }
                     let id = SurfaceId::new(self.id_ctr);
                     self.active_surface.replace(id);
                     cmds.push(text_input::focus(INPUT_ID.clone()));
+                    if resume {
+                        // typing replaces the resumed query
+                        cmds.push(text_input::select_all(INPUT_ID.clone()));
+                    }
                     cmds.push(commands::layer_surface::get_layer_surface(
                         SctkLayerSurfaceSettings {
                             id,
                             keyboard_interactivity: KeyboardInteractivity::Exclusive,
                             anchor: Anchor::TOP.union(Anchor::BOTTOM),
                             namespace: "launcher".into(),
-                            size: (Some(600), None),
+                            size: (Some(self.surface_width()), None),
                             ..Default::default()
                         },
                     ));
@@ -263,6 +2099,10 @@ impl Application for IcedLauncher {
                     return commands::layer_surface::destroy_layer_surface(id);
                 }
             }
+            Message::Activated => {
+                self.activated = true;
+                return self.update(Message::Hide);
+            }
         }
         Command::none()
     }
@@ -273,114 +2113,145 @@ impl Application for IcedLauncher {
             return vertical_space(Length::Units(1)).into();
         }
 
-        let launcher_entry = text_input(
-            "Type something...",
-            &self.input_value,
-            Message::InputChanged,
-        )
-        .on_submit(Message::Activate(None))
-        .padding(8)
-        .size(20)
-        .id(INPUT_ID.clone());
+        let placeholder = match self.mode {
+            Mode::Search => "Type something...".to_string(),
+            Mode::History => fl!("search-history"),
+            Mode::Clipboard => fl!("search-clipboard"),
+            Mode::Emoji => fl!("search-emoji"),
+            Mode::Windows => fl!("search-windows"),
+            Mode::Run => fl!("run-command"),
+            Mode::Apps => fl!("search-apps"),
+            Mode::OpenWith => fl!(
+                "search-open-with",
+                name = self
+                    .open_with
+                    .as_ref()
+                    .map(|file| file.name.clone())
+                    .unwrap_or_default()
+            ),
+        };
+        let launcher_entry = text_input(&placeholder, &self.input_value, Message::InputChanged)
+            .on_submit(Message::Activate(None))
+            .padding(8)
+            .size(20)
+            .id(INPUT_ID.clone());
 
         let clear_button = button("X").padding(10).on_press(Message::Clear);
+        let mut entry_row: Vec<Element<Message>> = vec![launcher_entry.into()];
+        // the mode entered with a prefix, left by clicking it or with
+        // Backspace in the empty input
+        if let Some(label) = self.mode_label() {
+            entry_row.push(highlighted(
+                button(text(label).size(14))
+                    .padding([4, 12])
+                    .on_press(Message::ExitMode)
+                    .style(Button::Text)
+                    .into(),
+                true,
+            ));
+        }
+        entry_row.push(clear_button.into());
 
-        let buttons = self
-            .launcher_items
-            .iter()
-            .enumerate()
-            .map(|(i, item)| {
-                let name = text(item.name.to_string())
-                    .horizontal_alignment(Horizontal::Left)
-                    .vertical_alignment(Vertical::Center);
-                let description = if item.description.len() > 40 {
-                    format!("{:.45}...", item.description)
-                } else {
-                    item.description.to_string()
-                };
-
-
-
-                let mut button_content = Vec::new();
-                if let Some(path) = item.category_icon.as_ref().and_then(|s| {
-                    let name = match s {
-                        IconSource::Name(name) | IconSource::Mime(name) => name
-                    };
-                    freedesktop_icons::lookup(&name)
-                        .with_theme("Pop")
-                        .with_size(32)
-                        .with_cache()
-                        .find()
-                }) {
-                    if path.extension() == Some(&OsStr::new("svg")) {
-                        button_content.push(svg::Svg::from_path(path)
-                        .width(Length::Units(24))
-                        .height(Length::Units(24))
-                        .style(Svg::Custom(|theme| iced_style::svg::Appearance {
-                            fill: Some(theme.palette().text),
-                        })).into())
-                    } else {
-                        button_content.push(Image::new(path)
-                        .width(Length::Units(24))
-                        .height(Length::Units(24)).into())
-                    }
-                }
-
-                if let Some(path) = item.icon.as_ref().and_then(|s| {
-                    let name = match s {
-                        IconSource::Name(name) | IconSource::Mime(name) => name
-                    };
-                    freedesktop_icons::lookup(&name)
-                        .with_theme("Pop")
-                        .with_size(24)
-                        .with_cache()
-                        .find()
-                }) {
-                    if path.extension() == Some(&OsStr::new("svg")) {
-                        button_content.push(svg::Svg::from_path(path)
-                        .width(Length::Units(24))
-                        .height(Length::Units(24)).into())
-                    } else {
-                        button_content.push(Image::new(path)
-                        .width(Length::Units(24))
-                        .height(Length::Units(24)).into())
-                    }
+        let favorites = self.visible_favorites();
+        let mut rows: Vec<Element<Message>> =
+            Vec::with_capacity(favorites.len() + self.launcher_items.len() + 2);
+        if let Some(error) = self.error.as_ref() {
+            rows.push(text(error).size(14).into());
+        }
+        if !favorites.is_empty() {
+            rows.push(text(fl!("favorites")).size(14).into());
+        }
+        for (i, favorite) in favorites.iter().enumerate() {
+            rows.push(self.result_row(
+                &favorite.result,
+                None,
+                i,
+                None,
+                Message::ActivateFavorite(i),
+            ));
+            rows.extend(self.context_rows(i));
+        }
+        match self.mode {
+            Mode::Windows => rows.extend(self.window_matches().into_iter().enumerate().map(
+                |(i, item)| {
+                    self.result_row(&item.result, None, i, Some(i), Message::Activate(Some(i)))
+                },
+            )),
+            Mode::Search => {
+                for (i, item) in self.launcher_items.iter().enumerate() {
+                    let row = favorites.len() + i;
+                    let thumbnail = item
+                        .uri
+                        .as_ref()
+                        .and_then(|uri| self.file_details.get(uri)?.as_ref())
+                        .and_then(|file| file.thumbnail.as_ref())
+                        // read for the preview pane as well
+                        .filter(|_| self.preferences.thumbnails);
+                    rows.push(self.result_row(
+                        &item.result,
+                        thumbnail,
+                        row,
+                        Some(i),
+                        Message::Activate(Some(i)),
+                    ));
+                    rows.extend(self.context_rows(row));
                 }
-
-                let description = text(description)
-                    .horizontal_alignment(Horizontal::Left)
-                    .vertical_alignment(Vertical::Center);
-
-                button_content.push(column![name, description].into());
-                button_content.push(
-                    container(
-                        text(format!("Ctrl + {i}"))
-                            .vertical_alignment(Vertical::Center)
-                            .horizontal_alignment(Horizontal::Right),
-                    )
-                    .width(Length::Fill)
-                    .center_y()
-                    .align_y(Vertical::Center)
-                    .align_x(Horizontal::Right)
-                    .into(),
+            }
+            Mode::History => rows.extend(
+                self.history_matches()
+                    .into_iter()
+                    .enumerate()
+                    .map(|(row, (age, query))| self.history_row(query, row, age)),
+            ),
+            Mode::Clipboard => rows.extend(
+                self.clipboard_matches()
+                    .into_iter()
+                    .enumerate()
+                    .map(|(row, (age, clip))| self.clip_row(clip, row, age)),
+            ),
+            Mode::Emoji => rows.extend(self.emoji_grid()),
+            Mode::Apps => {
+                rows.extend(self.app_grid());
+                rows.extend(self.context_rows(self.selected_item.unwrap_or_default()));
+            }
+            Mode::OpenWith => {
+                rows.push(text(fl!("open-with-hint")).size(14).into());
+                let default = self
+                    .open_with
+                    .as_ref()
+                    .and_then(|file| self.associations.default_handler(&self.apps, &file.mime))
+                    .map(|app| app.id.as_str());
+                rows.extend(
+                    self.handler_matches()
+                        .into_iter()
+                        .enumerate()
+                        .map(|(row, app)| {
+                            self.handler_row(app, row, default == Some(app.id.as_str()))
+                        }),
                 );
+            }
+            Mode::Run => rows.extend(
+                self.run_matches()
+                    .into_iter()
+                    .enumerate()
+                    .map(|(row, command)| self.command_row(command, row)),
+            ),
+        }
 
-                let btn = button(helpers::row(button_content).spacing(8).align_items(Alignment::Center))
-                    .width(Length::Fill)
-                    .on_press(Message::Activate(Some(i)))
-                    .padding([8, 16])
-                    .style(Button::Text);
-
-                btn.into()
-            })
-            .collect();
-
-        let content = column![
-            row![launcher_entry, clear_button].spacing(16),
-            helpers::column(buttons).spacing(8),
+        let results = column![
+            helpers::row(entry_row)
+                .spacing(16)
+                .align_items(Alignment::Center),
+            helpers::column(rows).spacing(8),
         ]
-        .spacing(16)
-        .max_width(600);
+        .spacing(16);
+        let content: Element<Message> = if self.preferences.preview_pane {
+            row![results.width(Length::Fill), self.preview()]
+                .spacing(24)
+                .into()
+        } else {
+            results.max_width(RESULTS_WIDTH as u32).into()
+        };
 
         column![
             button(vertical_space(Length::Units(1)))
@@ -409,27 +2280,75 @@ impl Application for IcedLauncher {
     }
 
     fn subscription(&self) -> Subscription<Message> {
+        let clipboard = if self.preferences.clipboard_history_size > 0 {
+            clipboard_watcher(0, self.preferences.clipboard_excluded_mime_types.clone())
+                .map(|(_, e)| Message::Clipboard(e))
+        } else {
+            Subscription::none()
+        };
         Subscription::batch(
             vec![
+                clipboard,
                 dbus_toggle(0).map(|e| match e {
                     (_, LauncherDbusEvent::Toggle) => Message::Toggle,
+                    (_, LauncherDbusEvent::Open(mode)) => Message::Open(mode),
+                    (_, LauncherDbusEvent::ClearHistory) => Message::ClearHistory,
+                    (_, LauncherDbusEvent::ClearQueryHistory) => Message::ClearQueryHistory,
                 }),
                 launcher(0).map(|(_, msg)| Message::LauncherEvent(msg)),
+                toplevels(0).map(|(_, e)| Message::Toplevel(e)),
                 events_with(|e, _status| match e {
                     cosmic::iced::Event::PlatformSpecific(PlatformSpecific::Wayland(
                         wayland::Event::Layer(e),
                     )) => Some(Message::Layer(e)),
+                    cosmic::iced::Event::Keyboard(iced::keyboard::Event::ModifiersChanged(
+                        modifiers,
+                    )) => Some(Message::Modifiers(modifiers)),
+                    cosmic::iced::Event::Mouse(mouse::Event::WheelScrolled { delta }) => {
+                        Some(Message::Scroll(match delta {
+                            mouse::ScrollDelta::Lines { y, .. } => y,
+                            mouse::ScrollDelta::Pixels { y, .. } => y / SCROLL_LINE_PIXELS,
+                        }))
+                    }
                     cosmic::iced::Event::Keyboard(iced::keyboard::Event::KeyReleased {
                         key_code,
                         modifiers,
                     }) => match key_code {
-                        KeyCode::Escape => Some(Message::Hide),
+                        KeyCode::Escape => Some(Message::Escape),
                         _ => None,
                     },
                     cosmic::iced::Event::Keyboard(iced::keyboard::Event::KeyPressed {
                         key_code,
                         modifiers,
                     }) => match key_code {
+                        KeyCode::Up if modifiers.control() && modifiers.shift() => {
+                            Some(Message::MoveFavorite(-1))
+                        }
+                        KeyCode::Down if modifiers.control() && modifiers.shift() => {
+                            Some(Message::MoveFavorite(1))
+                        }
+                        KeyCode::Up if modifiers.alt() => Some(Message::HistoryPrevious),
+                        KeyCode::Down if modifiers.alt() => Some(Message::HistoryNext),
+                        KeyCode::R if modifiers.control() => {
+                            Some(Message::ToggleMode(Mode::History))
+                        }
+                        KeyCode::V if modifiers.alt() => Some(Message::ToggleMode(Mode::Clipboard)),
+                        KeyCode::A if modifiers.alt() => Some(Message::ToggleMode(Mode::Apps)),
+                        KeyCode::Delete if modifiers.shift() => Some(Message::RemoveClip),
+                        KeyCode::F10 if modifiers.shift() => Some(Message::ToggleContext),
+                        KeyCode::Apps => Some(Message::ToggleContext),
+                        KeyCode::Tab if modifiers.shift() => Some(Message::Cycle(-1)),
+                        KeyCode::Tab => Some(Message::Cycle(1)),
+                        KeyCode::W if modifiers.control() => Some(Message::CloseWindow),
+                        KeyCode::Up => Some(Message::SelectPrevious),
+                        KeyCode::Down => Some(Message::SelectNext),
+                        KeyCode::Left => Some(Message::SelectLeft),
+                        KeyCode::Right => Some(Message::SelectRight),
+                        KeyCode::D if modifiers.control() => Some(Message::TogglePin),
+                        // Ctrl+C is left to the search input for copying the selected text
+                        KeyCode::C if modifiers.control() && modifiers.shift() => {
+                            Some(Message::Copy)
+                        }
                         KeyCode::Key1 | KeyCode::Numpad1 if modifiers.control() => {
                             Some(Message::Activate(Some(1)))
                         }
@@ -484,4 +2403,4 @@ impl Application for IcedLauncher {
     fn close_requested(&self, _id: iced_sctk::application::SurfaceIdWrapper) -> Self::Message {
         Message::Closed
     }
-}
\ No newline at end of file
+}
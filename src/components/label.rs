@@ -0,0 +1,492 @@
+use std::cell::{Ref, RefCell};
+use std::ops::Range;
+
+use cosmic::iced_native::alignment::{Horizontal, Vertical};
+use cosmic::iced_native::text::{self, Text};
+use cosmic::iced_native::widget::Tree;
+use cosmic::iced_native::{
+    layout, renderer, Color, Element, Layout, Length, Point, Rectangle, Size, Widget,
+};
+use unicode_bidi::BidiInfo;
+use unicode_segmentation::UnicodeSegmentation;
+
+const ELLIPSIS: &str = "…";
+
+/// Text with some of its byte ranges drawn in a highlight color, used for
+/// showing which parts of a result matched the query.
+///
+/// The label fills the available width, wraps onto at most `max_lines` lines
+/// and ellipsizes the last one at a grapheme boundary if the text still does
+/// not fit.
+pub struct Label {
+    content: String,
+    spans: Vec<Range<usize>>,
+    highlight: Color,
+    max_lines: usize,
+    measured: RefCell<Option<Measured>>,
+}
+
+/// A line of the label, as a byte range of the content.
+struct Line {
+    range: Range<usize>,
+    ellipsized: bool,
+}
+
+/// The lines of the label as they are drawn, measured for a size and width.
+///
+/// The label keeps them from its layout to its draws, so the text is not
+/// measured again until the label is rebuilt or the width changes.
+struct Measured {
+    size: u16,
+    max_width: f32,
+    lines: Vec<Shaped>,
+}
+
+/// A line in visual order, drawn from the left edge, or from the right one
+/// if its paragraph is right-to-left.
+struct Shaped {
+    segments: Vec<Segment>,
+    width: f32,
+    rtl: bool,
+}
+
+struct Segment {
+    content: String,
+    highlighted: bool,
+    width: f32,
+}
+
+impl Label {
+    pub fn new(content: impl Into<String>) -> Self {
+        Self {
+            content: content.into(),
+            spans: Vec::new(),
+            highlight: Color::BLACK,
+            max_lines: 1,
+            measured: RefCell::new(None),
+        }
+    }
+
+    /// Byte ranges of the content to highlight, sorted and non-overlapping.
+    pub fn spans(mut self, spans: Vec<Range<usize>>) -> Self {
+        self.spans = spans;
+        self
+    }
+
+    pub fn highlight(mut self, color: Color) -> Self {
+        self.highlight = color;
+        self
+    }
+
+    pub fn max_lines(mut self, max_lines: usize) -> Self {
+        self.max_lines = max_lines.max(1);
+        self
+    }
+
+    /// The lines fitting in `max_width`, measured again only if the size or
+    /// the width changed since the last call.
+    fn measured<Renderer: text::Renderer>(
+        &self,
+        renderer: &Renderer,
+        size: u16,
+        max_width: f32,
+    ) -> Ref<'_, Measured> {
+        let stale = self
+            .measured
+            .borrow()
+            .as_ref()
+            .map_or(true, |m| m.size != size || m.max_width != max_width);
+        if stale {
+            let measure = Measure::new(renderer, size);
+            let bidi = BidiInfo::new(&self.content, None);
+            let lines = break_lines(&self.content, self.max_lines, max_width, |text| {
+                measure.advance(text)
+            })
+            .into_iter()
+            .map(|line| self.shape(&measure, &bidi, line))
+            .collect();
+            *self.measured.borrow_mut() = Some(Measured {
+                size,
+                max_width,
+                lines,
+            });
+        }
+        Ref::map(self.measured.borrow(), |m| m.as_ref().unwrap())
+    }
+
+    /// Lays out `line` in visual order: the runs of each paragraph are
+    /// reordered, and the right-to-left ones are reversed along with their
+    /// highlights.
+    fn shape<Renderer: text::Renderer>(
+        &self,
+        measure: &Measure<'_, Renderer>,
+        bidi: &BidiInfo<'_>,
+        line: Line,
+    ) -> Shaped {
+        let rtl = bidi
+            .paragraphs
+            .iter()
+            .find(|p| p.range.contains(&line.range.start))
+            .map_or(false, |p| p.level.is_rtl());
+        let mut segments = Vec::new();
+
+        for paragraph in &bidi.paragraphs {
+            let range = line.range.start.max(paragraph.range.start)
+                ..line.range.end.min(paragraph.range.end);
+            if range.is_empty() {
+                continue;
+            }
+            let (levels, runs) = bidi.visual_runs(paragraph, range);
+            for run in runs {
+                let run_segments = self.segments(run.clone());
+                if levels[run.start].is_rtl() {
+                    segments.extend(run_segments.into_iter().rev().map(|(text, highlighted)| {
+                        (text.graphemes(true).rev().collect(), highlighted)
+                    }));
+                } else {
+                    segments.extend(
+                        run_segments
+                            .into_iter()
+                            .map(|(text, highlighted)| (text.to_string(), highlighted)),
+                    );
+                }
+            }
+        }
+
+        // the ellipsis ends the line, which is on the left if it reads right to left
+        if line.ellipsized {
+            let ellipsis = (ELLIPSIS.to_string(), false);
+            if rtl {
+                segments.insert(0, ellipsis);
+            } else {
+                segments.push(ellipsis);
+            }
+        }
+
+        let segments: Vec<Segment> = segments
+            .into_iter()
+            .map(|(content, highlighted)| Segment {
+                width: measure.advance(&content),
+                content,
+                highlighted,
+            })
+            .collect();
+        Shaped {
+            width: segments.iter().map(|s| s.width).sum(),
+            segments,
+            rtl,
+        }
+    }
+
+    /// Splits `range` of the content into consecutive segments, flagging the
+    /// highlighted ones.
+    fn segments(&self, range: Range<usize>) -> Vec<(&str, bool)> {
+        let mut segments = Vec::with_capacity(self.spans.len() * 2 + 1);
+        let mut cursor = range.start;
+        for span in &self.spans {
+            let (start, end) = (span.start.max(cursor), span.end.min(range.end));
+            if start >= end
+                || !self.content.is_char_boundary(start)
+                || !self.content.is_char_boundary(end)
+            {
+                continue;
+            }
+            if cursor < start {
+                segments.push((&self.content[cursor..start], false));
+            }
+            segments.push((&self.content[start..end], true));
+            cursor = end;
+        }
+        if cursor < range.end {
+            segments.push((&self.content[cursor..range.end], false));
+        }
+        segments
+    }
+}
+
+/// Breaks `content` into at most `max_lines` lines that fit in `max_width`
+/// as measured by `advance`, ellipsizing the last one if the rest does not fit.
+fn break_lines(
+    content: &str,
+    max_lines: usize,
+    max_width: f32,
+    advance: impl Fn(&str) -> f32,
+) -> Vec<Line> {
+    let mut lines = Vec::new();
+    let mut start = 0;
+
+    while start < content.len() && lines.len() < max_lines {
+        let rest = &content[start..];
+        if advance(rest) <= max_width {
+            lines.push(Line {
+                range: start..content.len(),
+                ellipsized: false,
+            });
+            break;
+        }
+
+        let last = lines.len() + 1 == max_lines;
+        let budget = if last {
+            max_width - advance(ELLIPSIS)
+        } else {
+            max_width
+        };
+        let boundaries: Vec<usize> = rest
+            .grapheme_indices(true)
+            .map(|(i, grapheme)| i + grapheme.len())
+            .collect();
+        let fitting = boundaries.partition_point(|&end| advance(&rest[..end]) <= budget);
+        // always make progress, even if a single grapheme is wider than the label
+        let mut end = boundaries[fitting.saturating_sub(1)];
+
+        if last {
+            end = rest[..end].trim_end().len();
+        } else if let Some(i) = rest[..end]
+            .rfind(char::is_whitespace)
+            .map(|i| rest[..i].trim_end().len())
+            .filter(|i| *i > 0)
+        {
+            end = i;
+        }
+
+        lines.push(Line {
+            range: start..start + end,
+            ellipsized: last,
+        });
+        start += end;
+        start += content[start..].len() - content[start..].trim_start().len();
+    }
+
+    lines
+}
+
+/// Measures text in the default font at one size.
+struct Measure<'a, Renderer> {
+    renderer: &'a Renderer,
+    size: u16,
+    /// Width of the sentinel appended to text ending with whitespace, which a
+    /// plain measurement would drop.
+    sentinel: f32,
+}
+
+impl<'a, Renderer: text::Renderer> Measure<'a, Renderer> {
+    const SENTINEL: &'static str = "|";
+
+    fn new(renderer: &'a Renderer, size: u16) -> Self {
+        let mut measure = Self {
+            renderer,
+            size,
+            sentinel: 0.0,
+        };
+        measure.sentinel = measure.width(Self::SENTINEL);
+        measure
+    }
+
+    /// Horizontal advance of `content`, including any trailing whitespace.
+    fn advance(&self, content: &str) -> f32 {
+        if content.ends_with(char::is_whitespace) {
+            self.width(&format!("{content}{}", Self::SENTINEL)) - self.sentinel
+        } else {
+            self.width(content)
+        }
+    }
+
+    fn width(&self, content: &str) -> f32 {
+        self.renderer
+            .measure(
+                content,
+                self.size,
+                Renderer::Font::default(),
+                Size::INFINITY,
+            )
+            .0
+    }
+}
+
+fn line_height<Renderer: text::Renderer>(renderer: &Renderer, size: u16) -> f32 {
+    renderer
+        .measure("|", size, Renderer::Font::default(), Size::INFINITY)
+        .1
+}
+
+impl<Message, Renderer> Widget<Message, Renderer> for Label
+where
+    Renderer: text::Renderer,
+{
+    fn width(&self) -> Length {
+        Length::Fill
+    }
+
+    fn height(&self) -> Length {
+        Length::Shrink
+    }
+
+    fn layout(&self, renderer: &Renderer, limits: &layout::Limits) -> layout::Node {
+        let limits = limits.width(Length::Fill).height(Length::Shrink);
+        let size = renderer.default_size();
+        let max_width = limits.max().width;
+        let measured = self.measured(renderer, size, max_width);
+        let lines = measured.lines.len().max(1);
+        let width = match measured.lines.as_slice() {
+            // right-to-left lines are drawn from the right edge
+            [line] if !line.rtl => line.width.min(max_width),
+            [] => 0.0,
+            _ => max_width,
+        };
+
+        layout::Node::new(
+            limits.resolve(Size::new(width, line_height(renderer, size) * lines as f32)),
+        )
+    }
+
+    fn draw(
+        &self,
+        _state: &Tree,
+        renderer: &mut Renderer,
+        _theme: &Renderer::Theme,
+        style: &renderer::Style,
+        layout: Layout<'_>,
+        _cursor_position: Point,
+        _viewport: &Rectangle,
+    ) {
+        let bounds = layout.bounds();
+        let size = renderer.default_size();
+        let line_height = line_height(renderer, size);
+        // the label was measured for the width it was laid out in, which
+        // may be larger than its bounds
+        let max_width = self
+            .measured
+            .borrow()
+            .as_ref()
+            .filter(|m| m.size == size)
+            .map_or(bounds.width, |m| m.max_width);
+        let measured = self.measured(renderer, size, max_width);
+
+        for (n, line) in measured.lines.iter().enumerate() {
+            let y = bounds.y + line_height * n as f32;
+            let mut x = if line.rtl {
+                bounds.x + bounds.width - line.width
+            } else {
+                bounds.x
+            };
+            for segment in &line.segments {
+                renderer.fill_text(Text {
+                    content: &segment.content,
+                    bounds: Rectangle {
+                        x,
+                        y,
+                        width: segment.width,
+                        height: line_height,
+                    },
+                    size: f32::from(size),
+                    color: if segment.highlighted {
+                        self.highlight
+                    } else {
+                        style.text_color
+                    },
+                    font: Renderer::Font::default(),
+                    horizontal_alignment: Horizontal::Left,
+                    vertical_alignment: Vertical::Top,
+                });
+                x += segment.width;
+            }
+        }
+    }
+}
+
+impl<'a, Message, Renderer> From<Label> for Element<'a, Message, Renderer>
+where
+    Renderer: text::Renderer + 'a,
+{
+    fn from(label: Label) -> Self {
+        Element::new(label)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One column per grapheme, two for wide ones such as CJK or emoji.
+    fn columns(text: &str) -> f32 {
+        text.graphemes(true)
+            .map(|g| {
+                if g.chars().any(|c| c >= '\u{2E80}') {
+                    2.0
+                } else {
+                    1.0
+                }
+            })
+            .sum()
+    }
+
+    /// The lines of `content` broken at `width` columns, with an ellipsis
+    /// marking the ellipsized one.
+    fn lines(content: &str, max_lines: usize, width: f32) -> Vec<String> {
+        break_lines(content, max_lines, width, columns)
+            .into_iter()
+            .map(|line| {
+                let text = &content[line.range];
+                if line.ellipsized {
+                    format!("{text}{ELLIPSIS}")
+                } else {
+                    text.to_string()
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn fits() {
+        assert_eq!(lines("Files", 1, 5.0), ["Files"]);
+        assert_eq!(lines("Files", 3, 80.0), ["Files"]);
+        assert!(lines("", 2, 10.0).is_empty());
+    }
+
+    #[test]
+    fn wraps_at_whitespace() {
+        assert_eq!(
+            lines("open the   file manager", 3, 10.0),
+            ["open the", "file", "manager"]
+        );
+        // words longer than the line are broken at any grapheme
+        assert_eq!(
+            lines("a preferences dialog", 4, 8.0),
+            ["a", "preferen", "ces", "dialog"]
+        );
+    }
+
+    #[test]
+    fn ellipsizes_the_last_line() {
+        assert_eq!(lines("open the file manager", 1, 10.0), ["open the…"]);
+        assert_eq!(
+            lines("open the file manager", 2, 10.0),
+            ["open the", "file mana…"]
+        );
+        // the ellipsis fits in the width
+        for line in lines("open the file manager", 2, 10.0) {
+            assert!(columns(&line) <= 10.0, "{line:?}");
+        }
+    }
+
+    #[test]
+    fn wide_graphemes() {
+        assert_eq!(
+            lines("日本語のテキスト", 3, 7.0),
+            ["日本語", "のテキ", "スト"]
+        );
+        assert_eq!(lines("日本語のテキスト", 2, 7.0), ["日本語", "のテキ…"]);
+        // the family emoji is one grapheme of several characters
+        let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+        assert_eq!(
+            lines(&format!("ab{family}cd"), 2, 3.0),
+            ["ab".to_string(), format!("{family}…")]
+        );
+    }
+
+    #[test]
+    fn grapheme_wider_than_the_line() {
+        assert_eq!(lines("日本", 3, 1.0), ["日", "本"]);
+        assert_eq!(lines("日本語", 1, 1.0), ["日…"]);
+    }
+}
@@ -1 +1,3 @@
 pub mod app;
+pub mod label;
+pub mod pointer;
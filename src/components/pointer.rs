@@ -0,0 +1,195 @@
+use cosmic::iced_native::event::{self, Event};
+use cosmic::iced_native::widget::{Operation, Tree};
+use cosmic::iced_native::{
+    layout, mouse, overlay, renderer, Clipboard, Element, Layout, Length, Point, Rectangle, Shell,
+    Widget,
+};
+
+/// Reports what the pointer does over a row, which its content does not
+/// handle: moving over it, and pressing it with the secondary or middle
+/// button.
+///
+/// Moving over the row is only reported while it is not selected, so the
+/// selection made with the keyboard stays until the pointer moves.
+pub struct Pointer<'a, Message, Renderer> {
+    content: Element<'a, Message, Renderer>,
+    selected: bool,
+    on_hover: Option<Message>,
+    on_right_press: Option<Message>,
+    on_middle_press: Option<Message>,
+}
+
+impl<'a, Message, Renderer> Pointer<'a, Message, Renderer> {
+    pub fn new(content: impl Into<Element<'a, Message, Renderer>>) -> Self {
+        Self {
+            content: content.into(),
+            selected: false,
+            on_hover: None,
+            on_right_press: None,
+            on_middle_press: None,
+        }
+    }
+
+    pub fn selected(mut self, selected: bool) -> Self {
+        self.selected = selected;
+        self
+    }
+
+    pub fn on_hover(mut self, message: Message) -> Self {
+        self.on_hover = Some(message);
+        self
+    }
+
+    pub fn on_right_press(mut self, message: Message) -> Self {
+        self.on_right_press = Some(message);
+        self
+    }
+
+    pub fn on_middle_press(mut self, message: Message) -> Self {
+        self.on_middle_press = Some(message);
+        self
+    }
+}
+
+impl<'a, Message, Renderer> Widget<Message, Renderer> for Pointer<'a, Message, Renderer>
+where
+    Message: Clone,
+    Renderer: renderer::Renderer,
+{
+    fn width(&self) -> Length {
+        self.content.as_widget().width()
+    }
+
+    fn height(&self) -> Length {
+        self.content.as_widget().height()
+    }
+
+    fn children(&self) -> Vec<Tree> {
+        vec![Tree::new(&self.content)]
+    }
+
+    fn diff(&self, tree: &mut Tree) {
+        tree.diff_children(std::slice::from_ref(&self.content));
+    }
+
+    // the content is laid out in place of the pointer, which has no layout
+    // of its own
+    fn layout(&self, renderer: &Renderer, limits: &layout::Limits) -> layout::Node {
+        self.content.as_widget().layout(renderer, limits)
+    }
+
+    fn operate(&self, tree: &mut Tree, layout: Layout<'_>, operation: &mut dyn Operation<Message>) {
+        self.content
+            .as_widget()
+            .operate(&mut tree.children[0], layout, operation);
+    }
+
+    fn on_event(
+        &mut self,
+        tree: &mut Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        renderer: &Renderer,
+        clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+    ) -> event::Status {
+        if let event::Status::Captured = self.content.as_widget_mut().on_event(
+            &mut tree.children[0],
+            event.clone(),
+            layout,
+            cursor_position,
+            renderer,
+            clipboard,
+            shell,
+        ) {
+            return event::Status::Captured;
+        }
+        if !layout.bounds().contains(cursor_position) {
+            return event::Status::Ignored;
+        }
+
+        let message = match event {
+            Event::Mouse(mouse::Event::CursorMoved { .. }) if !self.selected => {
+                // the other rows see the pointer move as well
+                if let Some(message) = self.on_hover.clone() {
+                    shell.publish(message);
+                }
+                return event::Status::Ignored;
+            }
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Right)) => {
+                self.on_right_press.clone()
+            }
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Middle)) => {
+                self.on_middle_press.clone()
+            }
+            _ => None,
+        };
+        match message {
+            Some(message) => {
+                shell.publish(message);
+                event::Status::Captured
+            }
+            None => event::Status::Ignored,
+        }
+    }
+
+    fn mouse_interaction(
+        &self,
+        tree: &Tree,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        viewport: &Rectangle,
+        renderer: &Renderer,
+    ) -> mouse::Interaction {
+        self.content.as_widget().mouse_interaction(
+            &tree.children[0],
+            layout,
+            cursor_position,
+            viewport,
+            renderer,
+        )
+    }
+
+    fn draw(
+        &self,
+        tree: &Tree,
+        renderer: &mut Renderer,
+        theme: &Renderer::Theme,
+        style: &renderer::Style,
+        layout: Layout<'_>,
+        cursor_position: Point,
+        viewport: &Rectangle,
+    ) {
+        self.content.as_widget().draw(
+            &tree.children[0],
+            renderer,
+            theme,
+            style,
+            layout,
+            cursor_position,
+            viewport,
+        );
+    }
+
+    fn overlay<'b>(
+        &'b self,
+        tree: &'b mut Tree,
+        layout: Layout<'_>,
+        renderer: &Renderer,
+    ) -> Option<overlay::Element<'b, Message, Renderer>> {
+        self.content
+            .as_widget()
+            .overlay(&mut tree.children[0], layout, renderer)
+    }
+}
+
+impl<'a, Message, Renderer> From<Pointer<'a, Message, Renderer>> for Element<'a, Message, Renderer>
+where
+    Message: Clone + 'a,
+    Renderer: renderer::Renderer + 'a,
+{
+    fn from(pointer: Pointer<'a, Message, Renderer>) -> Self {
+        Element::new(pointer)
+    }
+}
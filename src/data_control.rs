@@ -0,0 +1,478 @@
+//! Reading and setting the Wayland clipboard through the ext-data-control
+//! protocol, or wlr-data-control where it is missing.
+//!
+//! Unlike the data-device protocol, these let a client follow and replace
+//! the selection without keyboard focus or the serial of an input event. The
+//! clipboard is thus used over a connection of its own, which outlives the
+//! launcher surface: a selection is served by the client that set it for as
+//! long as it owns it.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::os::unix::io::{AsFd, OwnedFd};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context};
+use rustix::pipe::{pipe_with, PipeFlags};
+use wayland_client::backend::ObjectId;
+use wayland_client::globals::{registry_queue_init, GlobalList, GlobalListContents};
+use wayland_client::protocol::{wl_registry, wl_seat};
+use wayland_client::{
+    delegate_noop, event_created_child, Connection, Dispatch, EventQueue, Proxy, QueueHandle,
+};
+use wayland_protocols::ext::data_control::v1::client::{
+    ext_data_control_device_v1::{self, ExtDataControlDeviceV1},
+    ext_data_control_manager_v1::ExtDataControlManagerV1,
+    ext_data_control_offer_v1::{self, ExtDataControlOfferV1},
+    ext_data_control_source_v1::{self, ExtDataControlSourceV1},
+};
+use wayland_protocols_wlr::data_control::v1::client::{
+    zwlr_data_control_device_v1::{self, ZwlrDataControlDeviceV1},
+    zwlr_data_control_manager_v1::ZwlrDataControlManagerV1,
+    zwlr_data_control_offer_v1::{self, ZwlrDataControlOfferV1},
+    zwlr_data_control_source_v1::{self, ZwlrDataControlSourceV1},
+};
+
+/// How long the client owning the selection may take to send it.
+const READ_TIMEOUT: Duration = Duration::from_secs(2);
+/// Contents larger than this are not read.
+const MAX_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Follows the selection of the first seat.
+pub struct Watcher {
+    conn: Connection,
+    queue: EventQueue<State>,
+    state: State,
+    _device: Device,
+}
+
+impl Watcher {
+    pub fn new() -> anyhow::Result<Self> {
+        let (conn, globals, queue) = connect()?;
+        let qh = queue.handle();
+        let (manager, seat) = bind(&globals, &qh)?;
+        let device = manager.device(&seat, &qh);
+        // the current selection is announced right away, as a change
+        Ok(Self {
+            conn,
+            queue,
+            state: State::default(),
+            _device: device,
+        })
+    }
+
+    /// Blocks until the selection changes, and returns the MIME types it is
+    /// offered as, none if the clipboard was cleared.
+    pub fn changed(&mut self) -> anyhow::Result<Vec<String>> {
+        while !self.state.changed {
+            if self.state.finished {
+                return Err(anyhow!("the data control device was destroyed"));
+            }
+            self.queue.blocking_dispatch(&mut self.state)?;
+        }
+        self.state.changed = false;
+        Ok(self
+            .state
+            .selection
+            .as_ref()
+            .map(|offer| self.state.mime_types(offer))
+            .unwrap_or_default())
+    }
+
+    /// Reads the selection as `mime`.
+    pub fn read(&self, mime: &str) -> Option<Vec<u8>> {
+        let offer = self.state.selection.as_ref()?;
+        let (read, write) = pipe_with(PipeFlags::CLOEXEC).ok()?;
+        offer.receive(mime, write);
+        self.conn.flush().ok()?;
+
+        // a client may never close the pipe, which then leaves the reading
+        // thread behind rather than the watcher
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let mut data = Vec::new();
+            let res = File::from(read)
+                .take(MAX_SIZE)
+                .read_to_end(&mut data)
+                .map(|_| data);
+            let _ = tx.send(res);
+        });
+        match rx.recv_timeout(READ_TIMEOUT) {
+            Ok(Ok(data)) => Some(data),
+            Ok(Err(err)) => {
+                log::warn!("failed to read the clipboard as {mime}: {err}");
+                None
+            }
+            Err(_) => {
+                log::warn!("timed out reading the clipboard as {mime}");
+                None
+            }
+        }
+    }
+}
+
+/// Sets the selection to `contents`, by MIME type, and serves it until
+/// another client replaces it.
+///
+/// Blocks for as long as the selection is owned, and calls `ready` with the
+/// outcome of setting it as soon as it is known.
+pub fn serve(contents: Vec<(String, Arc<Vec<u8>>)>, ready: impl FnOnce(anyhow::Result<()>)) {
+    let mut owned = match own(contents) {
+        Ok(owned) => {
+            ready(Ok(()));
+            owned
+        }
+        Err(err) => {
+            ready(Err(err));
+            return;
+        }
+    };
+    while !owned.state.cancelled && !owned.state.finished {
+        if let Err(err) = owned.queue.blocking_dispatch(&mut owned.state) {
+            log::error!("failed to serve the clipboard: {err}");
+            break;
+        }
+    }
+}
+
+struct Owned {
+    _conn: Connection,
+    queue: EventQueue<State>,
+    state: State,
+    _device: Device,
+    _source: Source,
+}
+
+fn own(contents: Vec<(String, Arc<Vec<u8>>)>) -> anyhow::Result<Owned> {
+    let (conn, globals, mut queue) = connect()?;
+    let qh = queue.handle();
+    let (manager, seat) = bind(&globals, &qh)?;
+    let device = manager.device(&seat, &qh);
+    let source = manager.source(&qh);
+    for (mime, _) in &contents {
+        source.offer(mime.clone());
+    }
+    device.set_selection(&source);
+
+    let mut state = State {
+        contents: contents.into_iter().collect(),
+        ..State::default()
+    };
+    queue.roundtrip(&mut state)?;
+    if state.cancelled {
+        return Err(anyhow!("the selection was refused"));
+    }
+    Ok(Owned {
+        _conn: conn,
+        queue,
+        state,
+        _device: device,
+        _source: source,
+    })
+}
+
+fn connect() -> anyhow::Result<(Connection, GlobalList, EventQueue<State>)> {
+    let conn = Connection::connect_to_env().context("no Wayland display")?;
+    let (globals, queue) = registry_queue_init::<State>(&conn)?;
+    Ok((conn, globals, queue))
+}
+
+fn bind(
+    globals: &GlobalList,
+    qh: &QueueHandle<State>,
+) -> anyhow::Result<(Manager, wl_seat::WlSeat)> {
+    let manager = match globals.bind(qh, 1..=1, ()) {
+        Ok(manager) => Manager::Ext(manager),
+        Err(_) => Manager::Wlr(
+            globals
+                .bind(qh, 1..=1, ())
+                .context("the compositor supports no data control protocol")?,
+        ),
+    };
+    let seat = globals.bind(qh, 1..=1, ()).context("no seat")?;
+    Ok((manager, seat))
+}
+
+enum Manager {
+    Ext(ExtDataControlManagerV1),
+    Wlr(ZwlrDataControlManagerV1),
+}
+
+impl Manager {
+    fn device(&self, seat: &wl_seat::WlSeat, qh: &QueueHandle<State>) -> Device {
+        match self {
+            Self::Ext(manager) => Device::Ext(manager.get_data_device(seat, qh, ())),
+            Self::Wlr(manager) => Device::Wlr(manager.get_data_device(seat, qh, ())),
+        }
+    }
+
+    fn source(&self, qh: &QueueHandle<State>) -> Source {
+        match self {
+            Self::Ext(manager) => Source::Ext(manager.create_data_source(qh, ())),
+            Self::Wlr(manager) => Source::Wlr(manager.create_data_source(qh, ())),
+        }
+    }
+}
+
+impl Drop for Manager {
+    fn drop(&mut self) {
+        match self {
+            Self::Ext(manager) => manager.destroy(),
+            Self::Wlr(manager) => manager.destroy(),
+        }
+    }
+}
+
+enum Device {
+    Ext(ExtDataControlDeviceV1),
+    Wlr(ZwlrDataControlDeviceV1),
+}
+
+impl Device {
+    fn set_selection(&self, source: &Source) {
+        match (self, source) {
+            (Self::Ext(device), Source::Ext(source)) => device.set_selection(Some(source)),
+            (Self::Wlr(device), Source::Wlr(source)) => device.set_selection(Some(source)),
+            _ => unreachable!("sources are created by the manager of the device"),
+        }
+    }
+}
+
+impl Drop for Device {
+    fn drop(&mut self) {
+        match self {
+            Self::Ext(device) => device.destroy(),
+            Self::Wlr(device) => device.destroy(),
+        }
+    }
+}
+
+enum Source {
+    Ext(ExtDataControlSourceV1),
+    Wlr(ZwlrDataControlSourceV1),
+}
+
+impl Source {
+    fn offer(&self, mime: String) {
+        match self {
+            Self::Ext(source) => source.offer(mime),
+            Self::Wlr(source) => source.offer(mime),
+        }
+    }
+}
+
+impl Drop for Source {
+    fn drop(&mut self) {
+        match self {
+            Self::Ext(source) => source.destroy(),
+            Self::Wlr(source) => source.destroy(),
+        }
+    }
+}
+
+enum Offer {
+    Ext(ExtDataControlOfferV1),
+    Wlr(ZwlrDataControlOfferV1),
+}
+
+impl Offer {
+    fn id(&self) -> ObjectId {
+        match self {
+            Self::Ext(offer) => offer.id(),
+            Self::Wlr(offer) => offer.id(),
+        }
+    }
+
+    fn receive(&self, mime: &str, fd: OwnedFd) {
+        match self {
+            Self::Ext(offer) => offer.receive(mime.to_string(), fd.as_fd()),
+            Self::Wlr(offer) => offer.receive(mime.to_string(), fd.as_fd()),
+        }
+    }
+}
+
+impl Drop for Offer {
+    fn drop(&mut self) {
+        match self {
+            Self::Ext(offer) => offer.destroy(),
+            Self::Wlr(offer) => offer.destroy(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct State {
+    /// MIME types of the offers, as they are announced before the offer
+    /// becomes the selection.
+    offers: HashMap<ObjectId, Vec<String>>,
+    selection: Option<Offer>,
+    /// Set when the selection changes, until the watcher notices.
+    changed: bool,
+    finished: bool,
+    /// What the source serves, by MIME type.
+    contents: HashMap<String, Arc<Vec<u8>>>,
+    /// Set once the source no longer owns the selection.
+    cancelled: bool,
+}
+
+impl State {
+    fn mime_types(&self, offer: &Offer) -> Vec<String> {
+        self.offers.get(&offer.id()).cloned().unwrap_or_default()
+    }
+
+    fn offered(&mut self, offer: ObjectId, mime: String) {
+        self.offers.entry(offer).or_default().push(mime);
+    }
+
+    fn selected(&mut self, offer: Option<Offer>) {
+        // the previous offer is destroyed when dropped
+        let id = offer.as_ref().map(Offer::id);
+        self.offers.retain(|offer, _| Some(offer) == id.as_ref());
+        self.selection = offer;
+        self.changed = true;
+    }
+
+    fn send(&self, mime: &str, fd: OwnedFd) {
+        let data = match self.contents.get(mime) {
+            Some(data) => data.clone(),
+            None => return,
+        };
+        // the reader may be slow, or never read at all
+        thread::spawn(move || {
+            if let Err(err) = File::from(fd).write_all(&data) {
+                log::debug!("failed to send the clipboard: {err}");
+            }
+        });
+    }
+}
+
+impl Dispatch<wl_registry::WlRegistry, GlobalListContents> for State {
+    fn event(
+        _: &mut Self,
+        _: &wl_registry::WlRegistry,
+        _: wl_registry::Event,
+        _: &GlobalListContents,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+delegate_noop!(State: ignore wl_seat::WlSeat);
+delegate_noop!(State: ExtDataControlManagerV1);
+delegate_noop!(State: ZwlrDataControlManagerV1);
+
+impl Dispatch<ExtDataControlDeviceV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        _: &ExtDataControlDeviceV1,
+        event: ext_data_control_device_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        match event {
+            ext_data_control_device_v1::Event::Selection { id } => {
+                state.selected(id.map(Offer::Ext))
+            }
+            ext_data_control_device_v1::Event::Finished => state.finished = true,
+            _ => {}
+        }
+    }
+
+    event_created_child!(State, ExtDataControlDeviceV1, [
+        ext_data_control_device_v1::EVT_DATA_OFFER_OPCODE => (ExtDataControlOfferV1, ()),
+    ]);
+}
+
+impl Dispatch<ZwlrDataControlDeviceV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        _: &ZwlrDataControlDeviceV1,
+        event: zwlr_data_control_device_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwlr_data_control_device_v1::Event::Selection { id } => {
+                state.selected(id.map(Offer::Wlr))
+            }
+            zwlr_data_control_device_v1::Event::Finished => state.finished = true,
+            _ => {}
+        }
+    }
+
+    event_created_child!(State, ZwlrDataControlDeviceV1, [
+        zwlr_data_control_device_v1::EVT_DATA_OFFER_OPCODE => (ZwlrDataControlOfferV1, ()),
+    ]);
+}
+
+impl Dispatch<ExtDataControlOfferV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        offer: &ExtDataControlOfferV1,
+        event: ext_data_control_offer_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let ext_data_control_offer_v1::Event::Offer { mime_type } = event {
+            state.offered(offer.id(), mime_type);
+        }
+    }
+}
+
+impl Dispatch<ZwlrDataControlOfferV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        offer: &ZwlrDataControlOfferV1,
+        event: zwlr_data_control_offer_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let zwlr_data_control_offer_v1::Event::Offer { mime_type } = event {
+            state.offered(offer.id(), mime_type);
+        }
+    }
+}
+
+impl Dispatch<ExtDataControlSourceV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        _: &ExtDataControlSourceV1,
+        event: ext_data_control_source_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        match event {
+            ext_data_control_source_v1::Event::Send { mime_type, fd } => state.send(&mime_type, fd),
+            ext_data_control_source_v1::Event::Cancelled => state.cancelled = true,
+            _ => {}
+        }
+    }
+}
+
+impl Dispatch<ZwlrDataControlSourceV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        _: &ZwlrDataControlSourceV1,
+        event: zwlr_data_control_source_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwlr_data_control_source_v1::Event::Send { mime_type, fd } => {
+                state.send(&mime_type, fd)
+            }
+            zwlr_data_control_source_v1::Event::Cancelled => state.cancelled = true,
+            _ => {}
+        }
+    }
+}
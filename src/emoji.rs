@@ -0,0 +1,201 @@
+//! Emoji and symbol annotations for the emoji picker.
+//!
+//! The tables in `data/emoji/` are generated from the Emoji 13.1 data and
+//! the CLDR annotations by `build-aux/emoji-table.py`. Every line holds a
+//! glyph, its name and ` | ` separated keywords. `en.tsv` lists every fully
+//! qualified emoji and marks the ones taking a skin tone with a fourth
+//! column; the other tables only translate names and keywords.
+//!
+//! The emoji are followed by symbols such as arrows, math operators or
+//! currency signs. Those CLDR does not annotate are named after the Unicode
+//! character database, in English only, and can also be found by their
+//! block or code point (`u+2192`).
+
+use std::collections::HashMap;
+use std::io;
+use std::time::Duration;
+
+use i18n_embed::unic_langid::LanguageIdentifier;
+use rust_embed::RustEmbed;
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+
+#[derive(RustEmbed)]
+#[folder = "data/emoji/"]
+struct Tables;
+
+/// Skin tone applied to the emoji that take one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SkinTone {
+    #[default]
+    Default,
+    Light,
+    MediumLight,
+    Medium,
+    MediumDark,
+    Dark,
+}
+
+impl SkinTone {
+    pub const ALL: [SkinTone; 6] = [
+        SkinTone::Default,
+        SkinTone::Light,
+        SkinTone::MediumLight,
+        SkinTone::Medium,
+        SkinTone::MediumDark,
+        SkinTone::Dark,
+    ];
+
+    /// The Fitzpatrick modifier of the tone.
+    fn modifier(self) -> Option<char> {
+        match self {
+            SkinTone::Default => None,
+            SkinTone::Light => Some('\u{1F3FB}'),
+            SkinTone::MediumLight => Some('\u{1F3FC}'),
+            SkinTone::Medium => Some('\u{1F3FD}'),
+            SkinTone::MediumDark => Some('\u{1F3FE}'),
+            SkinTone::Dark => Some('\u{1F3FF}'),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Emoji {
+    pub glyph: String,
+    /// Name in the user's language, if the table for it has one.
+    pub name: String,
+    /// Lowercased keywords, in the user's language and in English.
+    keywords: Vec<String>,
+    skin_tones: bool,
+}
+
+impl Emoji {
+    /// The glyph with `tone` applied, if the emoji takes one.
+    ///
+    /// The modifier follows the first character, which also places it
+    /// correctly in sequences such as 🧑‍💻.
+    pub fn with_skin_tone(&self, tone: SkinTone) -> String {
+        let modifier = match tone.modifier() {
+            Some(modifier) if self.skin_tones => modifier,
+            _ => return self.glyph.clone(),
+        };
+        let mut chars = self.glyph.chars();
+        let mut glyph: String = chars.next().into_iter().collect();
+        glyph.push(modifier);
+        // the variation selector is replaced by the modifier
+        glyph.extend(chars.skip_while(|c| *c == '\u{FE0F}'));
+        glyph
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct EmojiTable {
+    emoji: Vec<Emoji>,
+}
+
+impl EmojiTable {
+    /// Loads the English table, translated by the table of the first of
+    /// `languages` that is bundled.
+    pub fn load(languages: &[LanguageIdentifier]) -> Self {
+        let english = table("en").unwrap_or_default();
+        let mut emoji: Vec<Emoji> = rows(&english)
+            .map(|row| Emoji {
+                glyph: row[0].to_string(),
+                name: row[1].to_string(),
+                keywords: keywords(&row),
+                skin_tones: row.get(3) == Some(&"t"),
+            })
+            .collect();
+
+        let translated = languages
+            .iter()
+            .map(|id| id.language.as_str())
+            .take_while(|language| *language != "en")
+            .find_map(table);
+        if let Some(translated) = translated {
+            let mut translations: HashMap<&str, (&str, Vec<String>)> = rows(&translated)
+                .map(|row| (row[0], (row[1], keywords(&row))))
+                .collect();
+            for e in &mut emoji {
+                if let Some((name, mut keywords)) = translations.remove(e.glyph.as_str()) {
+                    e.name = name.to_string();
+                    keywords.append(&mut e.keywords);
+                    e.keywords = keywords;
+                }
+            }
+        }
+
+        Self { emoji }
+    }
+
+    pub fn get(&self, glyph: &str) -> Option<&Emoji> {
+        self.emoji.iter().find(|e| e.glyph == glyph)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Emoji> {
+        self.emoji.iter()
+    }
+
+    /// Emoji with a keyword word starting with every word of `query`, those
+    /// whose name starts with the query first.
+    pub fn search(&self, query: &str) -> Vec<&Emoji> {
+        let query = query.trim().to_lowercase();
+        let words: Vec<&str> = query.split_whitespace().collect();
+        let mut matches: Vec<&Emoji> = self
+            .emoji
+            .iter()
+            .filter(|e| {
+                words.iter().all(|word| {
+                    e.keywords
+                        .iter()
+                        .flat_map(|keyword| keyword.split(' '))
+                        .any(|w| w.starts_with(word))
+                })
+            })
+            .collect();
+        matches.sort_by_key(|e| !e.name.to_lowercase().starts_with(&query));
+        matches
+    }
+}
+
+fn table(language: &str) -> Option<String> {
+    Tables::get(&format!("{language}.tsv"))
+        .map(|file| String::from_utf8_lossy(&file.data).into_owned())
+}
+
+/// The tab separated columns of the lines of `table`.
+fn rows(table: &str) -> impl Iterator<Item = Vec<&str>> {
+    table
+        .lines()
+        .map(|line| line.split('\t').collect::<Vec<_>>())
+        .filter(|row| row.len() >= 2)
+}
+
+/// The name and keywords of a row, lowercased.
+fn keywords(row: &[&str]) -> Vec<String> {
+    let keywords = row.get(2).into_iter().flat_map(|k| k.split(" | "));
+    std::iter::once(row[1])
+        .chain(keywords)
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// How long the compositor is given to focus the previous window once the
+/// launcher surface is destroyed.
+const FOCUS_DELAY: Duration = Duration::from_millis(150);
+
+/// Types `text` into the window focused after the launcher closed, through
+/// the virtual keyboard protocol used by `wtype`.
+pub async fn type_text(text: String) -> io::Result<()> {
+    // the keys would otherwise reach the closing launcher, or nothing
+    tokio::time::sleep(FOCUS_DELAY).await;
+    let status = Command::new("wtype").arg("--").arg(text).status().await?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            format!("wtype exited with {status}"),
+        ))
+    }
+}
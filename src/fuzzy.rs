@@ -0,0 +1,173 @@
+use std::ops::Range;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Computes the byte ranges of `haystack` that are matched by `pattern`.
+///
+/// Every whitespace separated token of the pattern is first looked up as a
+/// case insensitive substring and falls back to an in-order subsequence
+/// match. Tokens that can not be matched at all are skipped, so loosely
+/// related results still highlight whatever part of the query they share.
+/// The returned ranges are sorted, non-overlapping and merged, and cover
+/// whole graphemes.
+pub fn match_spans(pattern: &str, haystack: &str) -> Vec<Range<usize>> {
+    let hay: Vec<(usize, char)> = haystack.char_indices().collect();
+    let mut matched = vec![false; hay.len()];
+
+    for token in pattern.split_whitespace() {
+        let needle: Vec<char> = token.chars().collect();
+        let indices = substring(&needle, &hay).or_else(|| subsequence(&needle, &hay));
+        for i in indices.into_iter().flatten() {
+            matched[i] = true;
+        }
+    }
+
+    let mut spans: Vec<Range<usize>> = Vec::new();
+    let mut chars = hay.iter().zip(matched).peekable();
+    for (start, grapheme) in haystack.grapheme_indices(true) {
+        let end = start + grapheme.len();
+        // a grapheme is highlighted if any of its characters matched
+        let mut any = false;
+        while let Some((_, matched)) = chars.next_if(|((i, _), _)| *i < end) {
+            any |= matched;
+        }
+        if !any {
+            continue;
+        }
+        match spans.last_mut() {
+            Some(last) if last.end == start => last.end = end,
+            _ => spans.push(start..end),
+        }
+    }
+    spans
+}
+
+/// Whether every whitespace separated token of `pattern` matches `haystack`.
+pub fn is_match(pattern: &str, haystack: &str) -> bool {
+    let hay: Vec<(usize, char)> = haystack.char_indices().collect();
+    pattern.split_whitespace().all(|token| {
+        let needle: Vec<char> = token.chars().collect();
+        substring(&needle, &hay).is_some() || subsequence(&needle, &hay).is_some()
+    })
+}
+
+fn chars_eq(a: char, b: char) -> bool {
+    a == b || a.to_lowercase().eq(b.to_lowercase())
+}
+
+fn substring(needle: &[char], hay: &[(usize, char)]) -> Option<Vec<usize>> {
+    if needle.is_empty() || needle.len() > hay.len() {
+        return None;
+    }
+    (0..=hay.len() - needle.len())
+        .find(|&start| {
+            needle
+                .iter()
+                .zip(&hay[start..])
+                .all(|(n, (_, h))| chars_eq(*n, *h))
+        })
+        .map(|start| (start..start + needle.len()).collect())
+}
+
+fn subsequence(needle: &[char], hay: &[(usize, char)]) -> Option<Vec<usize>> {
+    if needle.is_empty() {
+        return None;
+    }
+    let mut indices = Vec::with_capacity(needle.len());
+    let mut needle = needle.iter().peekable();
+    for (i, (_, h)) in hay.iter().enumerate() {
+        match needle.peek() {
+            Some(n) if chars_eq(**n, *h) => {
+                indices.push(i);
+                needle.next();
+            }
+            Some(_) => {}
+            None => break,
+        }
+    }
+    needle.peek().is_none().then_some(indices)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The matched parts of `haystack`.
+    fn matched<'a>(pattern: &str, haystack: &'a str) -> Vec<&'a str> {
+        match_spans(pattern, haystack)
+            .into_iter()
+            .map(|span| &haystack[span])
+            .collect()
+    }
+
+    /// The byte offsets of the matched parts of `haystack`.
+    fn offsets(pattern: &str, haystack: &str) -> Vec<(usize, usize)> {
+        match_spans(pattern, haystack)
+            .into_iter()
+            .map(|span| (span.start, span.end))
+            .collect()
+    }
+
+    #[test]
+    fn substring_before_subsequence() {
+        assert_eq!(matched("fire", "Firefox"), ["Fire"]);
+        // the subsequence would start at the leading F
+        assert_eq!(matched("fox", "Firefox"), ["fox"]);
+        assert_eq!(matched("ffx", "Firefox"), ["F", "f", "x"]);
+        assert!(is_match("ffx", "Firefox"));
+    }
+
+    #[test]
+    fn tokens() {
+        assert_eq!(matched("term gnome", "GNOME Terminal"), ["GNOME", "Term"]);
+        assert!(is_match("term gnome", "GNOME Terminal"));
+        // unmatched tokens are skipped for highlighting, but fail the match
+        assert_eq!(matched("term xyz", "GNOME Terminal"), ["Term"]);
+        assert!(!is_match("term xyz", "GNOME Terminal"));
+        // overlapping tokens are merged
+        assert_eq!(matched("min ermi", "Terminal"), ["ermin"]);
+    }
+
+    #[test]
+    fn empty() {
+        assert!(match_spans("", "Files").is_empty());
+        assert!(match_spans("   ", "Files").is_empty());
+        assert!(match_spans("files", "").is_empty());
+        assert!(is_match("", "Files"));
+        assert!(!is_match("files", ""));
+        assert!(!is_match("filess", "Files"));
+    }
+
+    #[test]
+    fn case_folding() {
+        assert_eq!(matched("FILES", "files"), ["files"]);
+        assert_eq!(matched("straße", "STRASSE Straße"), ["Straße"]);
+        assert_eq!(matched("ΣΟΦΊΑ", "σοφία"), ["σοφία"]);
+        assert_eq!(matched("é", "CAFÉ"), ["É"]);
+    }
+
+    #[test]
+    fn multibyte_offsets() {
+        assert_eq!(offsets("ö", "Köln"), [(1, 3)]);
+        assert_eq!(offsets("ln", "Köln"), [(3, 5)]);
+        assert_eq!(offsets("kl", "Köln"), [(0, 1), (3, 4)]);
+        assert_eq!(matched("日本", "東京 日本語"), ["日本"]);
+        assert_eq!(matched("🦊", "Firefox 🦊 Browser"), ["🦊"]);
+        assert_eq!(matched("fb", "🦊 Firefox 🦊 Browser"), ["F", "B"]);
+    }
+
+    #[test]
+    fn whole_graphemes() {
+        // e followed by a combining acute accent
+        let haystack = "Cafe\u{301} Noir";
+        assert_eq!(matched("cafe", haystack), ["Cafe\u{301}"]);
+        assert_eq!(matched("fe n", haystack), ["fe\u{301}", "N"]);
+        // the family emoji is a single grapheme of several characters
+        let haystack = "a\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}b";
+        assert_eq!(
+            matched("\u{1F469}", haystack),
+            ["\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}"]
+        );
+        assert_eq!(matched("ab", haystack), ["a", "b"]);
+    }
+}
@@ -2,6 +2,7 @@
 
 use i18n_embed::{
     fluent::{fluent_language_loader, FluentLanguageLoader},
+    unic_langid::LanguageIdentifier,
     DefaultLocalizer, LanguageLoader, Localizer,
 };
 use once_cell::sync::Lazy;
@@ -37,9 +38,14 @@ pub fn localizer() -> Box<dyn Localizer> {
     Box::from(DefaultLocalizer::new(&*LANGUAGE_LOADER, &Localizations))
 }
 
+/// The languages of the user's locale, in order of preference.
+pub fn requested_languages() -> Vec<LanguageIdentifier> {
+    i18n_embed::DesktopLanguageRequester::requested_languages()
+}
+
 pub fn localize() {
     let localizer = localizer();
-    let requested_languages = i18n_embed::DesktopLanguageRequester::requested_languages();
+    let requested_languages = requested_languages();
 
     if let Err(error) = localizer.select(&requested_languages) {
         eprintln!("Error while loading language for App List {}", error);
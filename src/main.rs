@@ -1,8 +1,24 @@
+mod apps;
+mod cli;
+mod clipboard;
 mod components;
 #[rustfmt::skip]
 mod config;
+mod data_control;
+mod emoji;
+mod fuzzy;
 mod localize;
+mod mime_apps;
+mod preferences;
+mod preview;
+mod providers;
+mod run;
+mod spawn;
+mod store;
 mod subscriptions;
+mod thumbnails;
+mod toplevels;
+mod uri;
 use config::APP_ID;
 use log::info;
 
@@ -11,11 +27,47 @@ use localize::localize;
 use crate::{
     components::app,
     config::{PROFILE, VERSION},
+    store::{frecency::Frecency, query_history::QueryHistory},
 };
 
 fn main() -> cosmic::iced::Result {
     // Initialize logger
     pretty_env_logger::init();
+
+    match cli::parse() {
+        Ok(cli::Command::Run) => {}
+        Ok(cli::Command::Open(mode)) => {
+            if let Err(err) = cli::call("Open", &(mode,)) {
+                eprintln!("failed to open the launcher: {}", err);
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Ok(cli::Command::ClearHistory) => {
+            // a running launcher has to drop its in-memory copy as well
+            if let Err(err) = cli::call("ClearHistory", &()) {
+                info!("No running launcher to notify: {}", err);
+            }
+            Frecency::clear_stored();
+            return Ok(());
+        }
+        Ok(cli::Command::ClearQueryHistory) => {
+            if let Err(err) = cli::call("ClearQueryHistory", &()) {
+                info!("No running launcher to notify: {}", err);
+            }
+            QueryHistory::clear_stored();
+            return Ok(());
+        }
+        Ok(cli::Command::Help) => {
+            cli::usage();
+            return Ok(());
+        }
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    }
+
     info!("Iced Launcher ({})", APP_ID);
     info!("Version: {} ({})", VERSION, PROFILE);
 
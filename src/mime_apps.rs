@@ -0,0 +1,397 @@
+//! Applications associated with MIME types, following the `mimeapps.list`
+//! files of the XDG MIME applications associations specification and the
+//! `MimeType` keys of desktop entries.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::apps::{App, Apps};
+
+const GROUP_DEFAULTS: &str = "Default Applications";
+
+#[derive(Debug, Clone, Default)]
+pub struct Associations {
+    /// Desktop file IDs of the default applications, the preferred first.
+    defaults: HashMap<String, Vec<String>>,
+    /// Desktop file IDs added to the applications of a type.
+    added: HashMap<String, Vec<String>>,
+    /// Desktop file IDs removed from the applications of a type.
+    removed: HashMap<String, HashSet<String>>,
+    /// The most important list of the user, which defaults are written to.
+    user_list: Option<PathBuf>,
+}
+
+impl Associations {
+    /// Reads the lists of the XDG config and data directories.
+    pub fn load() -> Self {
+        let dirs = match xdg::BaseDirectories::new() {
+            Ok(dirs) => dirs,
+            Err(err) => {
+                log::warn!("failed to find the XDG directories: {err}");
+                return Self::default();
+            }
+        };
+        let config = std::iter::once(dirs.get_config_home()).chain(dirs.get_config_dirs());
+        let data = crate::apps::data_dirs()
+            .into_iter()
+            .map(|dir| dir.join("applications"));
+        Self::from_dirs(
+            &config.chain(data).collect::<Vec<_>>(),
+            &crate::apps::current_desktops(),
+        )
+    }
+
+    /// Reads the lists of `dirs`, from the most to the least important. The
+    /// first one holds the lists of the user.
+    ///
+    /// Every directory may hold a `$desktop-mimeapps.list` for each of the
+    /// current `desktops`, a `mimeapps.list` and the older `defaults.list`,
+    /// in that order.
+    pub fn from_dirs(dirs: &[PathBuf], desktops: &[String]) -> Self {
+        let names: Vec<String> = desktops
+            .iter()
+            .map(|desktop| format!("{}-mimeapps.list", desktop.to_lowercase()))
+            .chain(["mimeapps.list".to_string(), "defaults.list".to_string()])
+            .collect();
+        // a default written to `mimeapps.list` would be overridden by the
+        // list of the current desktop
+        let user_list = dirs.first().map(|dir| {
+            names[..names.len() - 1]
+                .iter()
+                .map(|name| dir.join(name))
+                .find(|path| path.exists())
+                .unwrap_or_else(|| dir.join("mimeapps.list"))
+        });
+        let mut associations = Self {
+            user_list,
+            ..Self::default()
+        };
+        for dir in dirs {
+            for name in &names {
+                for (group, mime, ids) in read_list(&dir.join(name)) {
+                    associations.insert(&group, mime, ids);
+                }
+            }
+        }
+        associations
+    }
+
+    fn insert(&mut self, group: &str, mime: String, ids: Vec<String>) {
+        match group {
+            GROUP_DEFAULTS => self.defaults.entry(mime).or_default().extend(ids),
+            "Added Associations" => {
+                // removals only apply to the less important lists read after
+                let removed = self.removed.get(&mime);
+                let added: Vec<String> = ids
+                    .into_iter()
+                    .filter(|id| removed.map_or(true, |r| !r.contains(id)))
+                    .collect();
+                self.added.entry(mime).or_default().extend(added);
+            }
+            "Removed Associations" => self.removed.entry(mime).or_default().extend(ids),
+            _ => {}
+        }
+    }
+
+    /// The installed applications handling `mime`: the defaults, then the
+    /// added associations, then the entries listing the type in their
+    /// `MimeType` key that were not removed.
+    pub fn handlers<'a>(&self, apps: &'a Apps, mime: &str) -> Vec<&'a App> {
+        let removed = self.removed.get(mime);
+        let defaults = self.defaults.get(mime).into_iter().flatten();
+        let added = self.added.get(mime).into_iter().flatten();
+        let listed = apps
+            .with_mime_type(mime)
+            .into_iter()
+            .filter(|app| removed.map_or(true, |r| !r.contains(&app.id)));
+
+        let mut seen = HashSet::new();
+        defaults
+            .chain(added)
+            .filter_map(|id| apps.get(id))
+            .chain(listed)
+            .filter(|app| seen.insert(app.id.as_str()))
+            .collect()
+    }
+
+    /// The installed default application of `mime`.
+    pub fn default_handler<'a>(&self, apps: &'a Apps, mime: &str) -> Option<&'a App> {
+        self.defaults.get(mime)?.iter().find_map(|id| apps.get(id))
+    }
+
+    /// Makes the application `id` the default of `mime` in the list of the
+    /// user.
+    pub fn set_default(&mut self, mime: &str, id: &str) -> io::Result<()> {
+        let path = self
+            .user_list
+            .as_ref()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "no mimeapps.list of the user"))?;
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(err),
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, with_default(&contents, mime, id))?;
+
+        let defaults = self.defaults.entry(mime.to_string()).or_default();
+        defaults.retain(|d| d != id);
+        defaults.insert(0, id.to_string());
+        Ok(())
+    }
+}
+
+/// `contents` of a list with the default of `mime` replaced by `id`, keeping
+/// everything else as is.
+fn with_default(contents: &str, mime: &str, id: &str) -> String {
+    let mut lines: Vec<String> = contents.lines().map(str::to_string).collect();
+    let line = format!("{mime}={id};");
+    let header = format!("[{GROUP_DEFAULTS}]");
+    match lines.iter().position(|l| l.trim() == header) {
+        Some(start) => {
+            let group = start + 1;
+            let end = lines[group..]
+                .iter()
+                .position(|l| l.trim_start().starts_with('['))
+                .map_or(lines.len(), |i| group + i);
+            let existing = lines[group..end].iter().position(|l| {
+                l.split_once('=')
+                    .map_or(false, |(key, _)| key.trim() == mime)
+            });
+            match existing {
+                Some(i) => lines[group + i] = line,
+                None => {
+                    // after the last entry, before the blank lines between groups
+                    let last = lines[group..end]
+                        .iter()
+                        .rposition(|l| !l.trim().is_empty())
+                        .map_or(group, |i| group + i + 1);
+                    lines.insert(last, line);
+                }
+            }
+        }
+        None => {
+            if lines.last().map_or(false, |l| !l.trim().is_empty()) {
+                lines.push(String::new());
+            }
+            lines.push(header);
+            lines.push(line);
+        }
+    }
+    lines.join("\n") + "\n"
+}
+
+/// The `key=id;id;` lines of a list, along with their group.
+fn read_list(path: &Path) -> Vec<(String, String, Vec<String>)> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(_) => return Vec::new(),
+    };
+    let mut group = String::new();
+    let mut entries = Vec::new();
+    for line in contents.lines().map(str::trim) {
+        if line.starts_with('#') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            group = name.to_string();
+        } else if let Some((mime, ids)) = line.split_once('=') {
+            let ids = ids
+                .split(';')
+                .map(str::trim)
+                .filter(|id| !id.is_empty())
+                .map(str::to_string)
+                .collect();
+            entries.push((group.clone(), mime.trim().to_string(), ids));
+        }
+    }
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A temporary directory holding `files`, by their relative path.
+    fn fixture(files: &[(&str, &str)]) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for (path, contents) in files {
+            let path = dir.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+        dir
+    }
+
+    fn entry(name: &str, mime_types: &str) -> String {
+        format!(
+            "[Desktop Entry]\nType=Application\nName={name}\nExec={name}\nMimeType={mime_types}\n"
+        )
+    }
+
+    fn ids(apps: Vec<&App>) -> Vec<&str> {
+        apps.into_iter().map(|app| app.id.as_str()).collect()
+    }
+
+    #[test]
+    fn desktop_lists_first() {
+        let dir = fixture(&[
+            (
+                "user/cosmic-mimeapps.list",
+                "[Default Applications]\ntext/plain=desktop.desktop;\n",
+            ),
+            (
+                "user/mimeapps.list",
+                "[Default Applications]\ntext/plain=generic.desktop;\nimage/png=generic.desktop;\n",
+            ),
+            (
+                "system/cosmic-mimeapps.list",
+                "[Default Applications]\nimage/png=system.desktop;\n",
+            ),
+            ("data/applications/desktop.desktop", &entry("desktop", "")),
+            ("data/applications/generic.desktop", &entry("generic", "")),
+            ("data/applications/system.desktop", &entry("system", "")),
+        ]);
+        let apps = Apps::from_dirs(&[dir.path().join("data")], None);
+        let associations = Associations::from_dirs(
+            &[dir.path().join("user"), dir.path().join("system")],
+            &["COSMIC".to_string()],
+        );
+
+        let default = |mime| {
+            associations
+                .default_handler(&apps, mime)
+                .map(|app| app.id.as_str())
+        };
+        assert_eq!(default("text/plain"), Some("desktop.desktop"));
+        // the lists of the user take priority over those of the desktop
+        assert_eq!(default("image/png"), Some("generic.desktop"));
+        assert_eq!(
+            ids(associations.handlers(&apps, "text/plain")),
+            ["desktop.desktop", "generic.desktop"]
+        );
+        // new defaults would otherwise be overridden by the desktop list
+        assert_eq!(
+            associations.user_list,
+            Some(dir.path().join("user/cosmic-mimeapps.list"))
+        );
+    }
+
+    #[test]
+    fn removals_apply_to_less_important_lists() {
+        let dir = fixture(&[
+            (
+                "user/mimeapps.list",
+                "[Added Associations]\ntext/plain=first.desktop;\n\n\
+                 [Removed Associations]\ntext/plain=second.desktop;listed.desktop;\n",
+            ),
+            (
+                "system/mimeapps.list",
+                "[Added Associations]\ntext/plain=second.desktop;third.desktop;\n\n\
+                 [Removed Associations]\ntext/plain=first.desktop;\n",
+            ),
+            ("data/applications/first.desktop", &entry("first", "")),
+            ("data/applications/second.desktop", &entry("second", "")),
+            ("data/applications/third.desktop", &entry("third", "")),
+            (
+                "data/applications/listed.desktop",
+                &entry("listed", "text/plain;"),
+            ),
+        ]);
+        let apps = Apps::from_dirs(&[dir.path().join("data")], None);
+        let associations =
+            Associations::from_dirs(&[dir.path().join("user"), dir.path().join("system")], &[]);
+
+        assert_eq!(
+            ids(associations.handlers(&apps, "text/plain")),
+            ["first.desktop", "third.desktop"]
+        );
+    }
+
+    #[test]
+    fn mime_type_fallback() {
+        let dir = fixture(&[
+            (
+                "user/mimeapps.list",
+                "[Default Applications]\ntext/plain=editor.desktop;\n",
+            ),
+            (
+                "data/applications/editor.desktop",
+                &entry("editor", "text/plain;"),
+            ),
+            (
+                "data/applications/viewer.desktop",
+                &entry("viewer", "image/png;text/plain;"),
+            ),
+            (
+                "data/applications/other.desktop",
+                &entry("other", "image/png;"),
+            ),
+        ]);
+        let apps = Apps::from_dirs(&[dir.path().join("data")], None);
+        let associations = Associations::from_dirs(&[dir.path().join("user")], &[]);
+
+        // listed once, after the default
+        assert_eq!(
+            ids(associations.handlers(&apps, "text/plain")),
+            ["editor.desktop", "viewer.desktop"]
+        );
+        assert_eq!(
+            ids(associations.handlers(&apps, "image/png")),
+            ["other.desktop", "viewer.desktop"]
+        );
+        assert!(associations.default_handler(&apps, "image/png").is_none());
+    }
+
+    #[test]
+    fn with_default_keeps_the_rest() {
+        let list = "# written by hand\n\
+                    [Default Applications]\n\
+                    text/plain=old.desktop;\n\
+                    image/png=viewer.desktop;\n\
+                    \n\
+                    [Added Associations]\n\
+                    text/html=browser.desktop;\n";
+        assert_eq!(
+            with_default(list, "text/plain", "new.desktop"),
+            "# written by hand\n\
+             [Default Applications]\n\
+             text/plain=new.desktop;\n\
+             image/png=viewer.desktop;\n\
+             \n\
+             [Added Associations]\n\
+             text/html=browser.desktop;\n"
+        );
+        assert_eq!(
+            with_default(list, "text/html", "browser.desktop"),
+            "# written by hand\n\
+             [Default Applications]\n\
+             text/plain=old.desktop;\n\
+             image/png=viewer.desktop;\n\
+             text/html=browser.desktop;\n\
+             \n\
+             [Added Associations]\n\
+             text/html=browser.desktop;\n"
+        );
+        assert_eq!(
+            with_default(
+                "[Added Associations]\ntext/html=browser.desktop;",
+                "text/html",
+                "browser.desktop"
+            ),
+            "[Added Associations]\n\
+             text/html=browser.desktop;\n\
+             \n\
+             [Default Applications]\n\
+             text/html=browser.desktop;\n"
+        );
+        assert_eq!(
+            with_default("", "text/html", "browser.desktop"),
+            "[Default Applications]\ntext/html=browser.desktop;\n"
+        );
+    }
+}
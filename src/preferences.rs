@@ -0,0 +1,208 @@
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+
+use crate::providers;
+
+/// Directory name used below the XDG base directories.
+pub const PREFIX: &str = "iced-launcher";
+const FILE: &str = "preferences.json";
+
+/// User preferences, read from `$XDG_CONFIG_HOME/iced-launcher/preferences.json`.
+///
+/// Missing keys fall back to their defaults, so the file only needs to
+/// contain the options a user wants to change.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Preferences {
+    /// Let result descriptions wrap onto a second line before being ellipsized.
+    pub two_line_descriptions: bool,
+    /// Show the details of the selected result next to the results, which
+    /// makes the launcher wider.
+    pub preview_pane: bool,
+    /// Show the thumbnails of files in the results instead of the icons of
+    /// their type, asking the thumbnailer service for the missing ones. The
+    /// preview pane shows them either way.
+    pub thumbnails: bool,
+    /// Rank results that were launched often and recently first.
+    pub frecency: bool,
+    /// Seconds during which reopening the launcher restores the last query,
+    /// results and selection. `0` always starts from an empty query.
+    pub resume_timeout: u64,
+    /// Show the result of arithmetic queries, copied to the clipboard on
+    /// activation.
+    pub calculator: bool,
+    /// Show recently used files matching the query.
+    pub recent_files: bool,
+    /// Search the names of files and directories when the query starts
+    /// with `/` or `~`.
+    pub file_search: bool,
+    /// Directories searched by queries starting with `~`, which stands for
+    /// the home directory. Hidden and ignored files below them are skipped.
+    /// Queries starting with `/` search below the directory they name.
+    pub file_search_roots: Vec<String>,
+    /// List the hosts of the SSH config and of the known hosts when the
+    /// query starts with `ssh`.
+    pub ssh_hosts: bool,
+    /// Offer to open web addresses, and to search the web when the query
+    /// starts with the keyword of one of `search_engines`.
+    pub web_search: bool,
+    pub search_engines: Vec<SearchEngine>,
+    /// Search with the executables in
+    /// `$XDG_CONFIG_HOME/iced-launcher/scripts/`.
+    pub scripts: bool,
+    /// Starts of the query that switch to a mode, or search with a single
+    /// provider.
+    pub prefixes: Vec<Prefix>,
+    /// Number of clipboard contents remembered for the clipboard history.
+    /// `0` stops watching the clipboard.
+    pub clipboard_history_size: usize,
+    /// Keep the clipboard history across sessions.
+    pub clipboard_history_persist: bool,
+    /// Clipboard contents offering any of these MIME types are not
+    /// remembered. Password managers use them to mark secrets.
+    pub clipboard_excluded_mime_types: Vec<String>,
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Self {
+            two_line_descriptions: false,
+            preview_pane: false,
+            thumbnails: true,
+            frecency: true,
+            resume_timeout: 0,
+            calculator: true,
+            recent_files: true,
+            file_search: true,
+            file_search_roots: vec!["~".to_string()],
+            ssh_hosts: true,
+            web_search: true,
+            search_engines: default_search_engines(),
+            scripts: true,
+            prefixes: default_prefixes(),
+            clipboard_history_size: 50,
+            clipboard_history_persist: false,
+            clipboard_excluded_mime_types: vec!["x-kde-passwordManagerHint".to_string()],
+        }
+    }
+}
+
+/// A web search, started by typing its keyword before the search terms.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchEngine {
+    /// Such as `g`, which may also be typed as the bang `!g`.
+    pub keyword: String,
+    pub name: String,
+    /// The address of the results, `%s` standing for the search terms.
+    pub url: String,
+}
+
+fn default_search_engines() -> Vec<SearchEngine> {
+    [
+        ("g", "Google", "https://www.google.com/search?q=%s"),
+        ("ddg", "DuckDuckGo", "https://duckduckgo.com/?q=%s"),
+        // not `w`, since `w ` is the prefix of the window switcher, which
+        // is routed before the web search sees the query
+        (
+            "wiki",
+            "Wikipedia",
+            "https://en.wikipedia.org/w/index.php?search=%s",
+        ),
+        ("gh", "GitHub", "https://github.com/search?q=%s"),
+        ("crates", "crates.io", "https://crates.io/search?q=%s"),
+        (
+            "docs",
+            "Docs.rs",
+            "https://docs.rs/releases/search?query=%s",
+        ),
+        (
+            "yt",
+            "YouTube",
+            "https://www.youtube.com/results?search_query=%s",
+        ),
+    ]
+    .into_iter()
+    .map(|(keyword, name, url)| SearchEngine {
+        keyword: keyword.to_string(),
+        name: name.to_string(),
+        url: url.to_string(),
+    })
+    .collect()
+}
+
+/// A start of the query that is routed to a mode or to a provider.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Prefix {
+    pub prefix: String,
+    /// A mode such as `emoji` or `run`, or the name of a provider such as
+    /// `calculator`, which is then the only one searched.
+    pub target: String,
+}
+
+fn default_prefixes() -> Vec<Prefix> {
+    [
+        ("=", "calculator"),
+        (":", "emoji"),
+        (">", "run"),
+        ("?", "web"),
+        ("w ", "windows"),
+    ]
+    .into_iter()
+    .map(|(prefix, target)| Prefix {
+        prefix: prefix.to_string(),
+        target: target.to_string(),
+    })
+    .collect()
+}
+
+impl Preferences {
+    /// The target of the longest prefix `query` starts with, and the rest of
+    /// the query.
+    pub fn route<'a>(&self, query: &'a str) -> Option<(&str, &'a str)> {
+        self.prefixes
+            .iter()
+            .filter(|p| !p.prefix.is_empty())
+            .filter_map(|p| Some((p, query.strip_prefix(p.prefix.as_str())?)))
+            .max_by_key(|(p, _)| p.prefix.len())
+            .map(|(p, rest)| (p.target.as_str(), rest))
+    }
+
+    /// Leaves out the prefixes whose target is neither one of `modes` nor a
+    /// local provider, which would search nothing.
+    pub fn check_prefixes(&mut self, modes: &[&str]) {
+        self.prefixes.retain(|p| {
+            let target = p.target.as_str();
+            let known = modes.contains(&target) || providers::NAMES.contains(&target);
+            if !known {
+                log::warn!(
+                    "ignoring the prefix '{}' of the unknown target '{target}'",
+                    p.prefix
+                );
+            }
+            known
+        });
+    }
+
+    pub fn load() -> Self {
+        let path = match xdg::BaseDirectories::with_prefix(PREFIX)
+            .ok()
+            .and_then(|dirs| dirs.find_config_file(FILE))
+        {
+            Some(path) => path,
+            None => return Self::default(),
+        };
+
+        match fs::read_to_string(&path).map(|s| serde_json::from_str(&s)) {
+            Ok(Ok(preferences)) => preferences,
+            Ok(Err(err)) => {
+                log::error!("invalid preferences in {}: {}", path.display(), err);
+                Self::default()
+            }
+            Err(err) => {
+                log::error!("failed to read {}: {}", path.display(), err);
+                Self::default()
+            }
+        }
+    }
+}
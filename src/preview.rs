@@ -0,0 +1,88 @@
+//! Details of the files results stand for, shown in the preview pane, and
+//! their thumbnails, shown in the rows as well.
+//!
+//! They are read on a blocking thread as the results arrive, so drawing the
+//! launcher never waits for the file system or the thumbnailer. Images are
+//! only shown through their thumbnails, which are small enough to be decoded
+//! while drawing, unlike the originals.
+
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+
+use crate::{fl, thumbnails, uri};
+
+#[derive(Debug, Clone)]
+pub struct FileDetails {
+    pub path: PathBuf,
+    pub mime: String,
+    /// Size in bytes, unless the file is a directory.
+    pub size: Option<u64>,
+    pub modified: Option<SystemTime>,
+    pub thumbnail: Option<PathBuf>,
+}
+
+/// Reads the details of the local file `uri`, if it exists, along with its
+/// thumbnail.
+pub async fn load(uri: String) -> Option<FileDetails> {
+    let read_uri = uri.clone();
+    let mut details = tokio::task::spawn_blocking(move || read(&read_uri))
+        .await
+        .ok()
+        .flatten()?;
+    // directories have no thumbnails
+    if details.size.is_some() {
+        if let Some(modified) = details.modified {
+            details.thumbnail = thumbnails::thumbnail(uri, details.mime.clone(), modified).await;
+        }
+    }
+    Some(details)
+}
+
+fn read(uri: &str) -> Option<FileDetails> {
+    let path = uri::to_path(uri)?;
+    let metadata = std::fs::metadata(&path).ok()?;
+    let mime = if metadata.is_dir() {
+        "inode/directory".to_string()
+    } else {
+        mime_guess::from_path(&path)
+            .first_or_octet_stream()
+            .essence_str()
+            .to_string()
+    };
+    Some(FileDetails {
+        size: metadata.is_file().then(|| metadata.len()),
+        modified: metadata.modified().ok(),
+        path,
+        mime,
+        thumbnail: None,
+    })
+}
+
+/// `bytes` in the largest unit keeping it above one, such as `1.5 MB`.
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "kB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1000.0 && unit < UNITS.len() - 1 {
+        size /= 1000.0;
+        unit += 1;
+    }
+    match unit {
+        0 => format!("{bytes} B"),
+        _ => format!("{size:.1} {}", UNITS[unit]),
+    }
+}
+
+/// How long ago `time` was, such as `3 hours ago`.
+pub fn format_age(time: SystemTime) -> String {
+    let secs = SystemTime::now()
+        .duration_since(time)
+        .unwrap_or(Duration::ZERO)
+        .as_secs();
+    match secs {
+        0..=59 => fl!("age-now"),
+        60..=3599 => fl!("age-minutes", n = secs / 60),
+        3600..=86399 => fl!("age-hours", n = secs / 3600),
+        _ => fl!("age-days", n = secs / 86400),
+    }
+}
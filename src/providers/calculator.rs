@@ -0,0 +1,687 @@
+//! Evaluates arithmetic expressions as they are typed.
+//!
+//! Supports `+ - * / ^ %` with the usual precedence, `mod`, parentheses,
+//! functions such as `sqrt(2)` or `max(1, 2)`, the constants `pi` and `e`,
+//! hexadecimal, octal and binary literals, percentages (`200 + 10%`), and
+//! units of length, mass, time and data that can be converted with
+//! `to`/`in` (`5 km to mi`). `to hex`, `to oct` and `to bin` format the
+//! result in another base.
+
+use std::{borrow::Cow, f64::consts};
+
+use futures::stream::BoxStream;
+use pop_launcher::{IconSource, SearchResult};
+
+use super::{ready, Action, Provider, ProviderResult};
+
+const ICON: &str = "accessories-calculator";
+
+pub struct Calculator;
+
+impl Provider for Calculator {
+    fn name(&self) -> &'static str {
+        "calculator"
+    }
+
+    fn search(&mut self, query: &str) -> BoxStream<'static, Vec<ProviderResult>> {
+        let value = match evaluate(query) {
+            Ok(value) => value,
+            Err(_) => return ready(Vec::new()),
+        };
+
+        ready(vec![ProviderResult {
+            result: SearchResult {
+                id: 0,
+                name: value.clone(),
+                description: query.trim().to_string(),
+                icon: Some(IconSource::Name(Cow::Borrowed(ICON))),
+                category_icon: Some(IconSource::Name(Cow::Borrowed(ICON))),
+                window: None,
+            },
+            action: Action::Copy(value),
+            options: Vec::new(),
+            details: Vec::new(),
+        }])
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    /// The query is not an expression, e.g. a plain word or number.
+    NotAnExpression,
+    Syntax,
+    UnknownIdentifier,
+    /// A function was called with the wrong number of arguments.
+    Arity,
+    IncompatibleUnits,
+    /// Base conversion of a value that is not an integer.
+    NotAnInteger,
+    NotFinite,
+}
+
+/// Evaluates `input` and formats the result.
+pub fn evaluate(input: &str) -> Result<String, Error> {
+    let tokens = tokenize(input)?;
+    if tokens.is_empty() {
+        return Err(Error::NotAnExpression);
+    }
+
+    let mut parser = Parser {
+        tokens,
+        pos: 0,
+        computed: false,
+    };
+    let value = parser.expression()?;
+    let formatted = parser.conversion(value)?;
+    if parser.pos < parser.tokens.len() {
+        return Err(Error::Syntax);
+    }
+    // a lone number such as `-5` or `5 km` is a search, not a calculation
+    if !parser.computed {
+        return Err(Error::NotAnExpression);
+    }
+    Ok(formatted)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Ident(String),
+    Op(char),
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, Error> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c.is_ascii_digit() || c == '.' {
+            let mut literal = String::new();
+            while let Some(&c) = chars.peek() {
+                // the sign of an exponent, as in `5e-3`
+                let exponent_sign = (c == '+' || c == '-')
+                    && awaits_exponent(&literal)
+                    && chars.clone().nth(1).map_or(false, |c| c.is_ascii_digit());
+                if c.is_ascii_alphanumeric() || c == '.' || c == '_' || exponent_sign {
+                    literal.push(c);
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            tokens.extend(number(&literal)?);
+        } else if c.is_alphabetic() {
+            let mut ident = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_alphanumeric() || c == '_' {
+                    ident.push(c);
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            tokens.push(Token::Ident(ident.to_lowercase()));
+        } else if "+-*/^%(),×÷".contains(c) {
+            tokens.push(Token::Op(match c {
+                '×' => '*',
+                '÷' => '/',
+                c => c,
+            }));
+            chars.next();
+        } else {
+            return Err(Error::Syntax);
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Whether `literal` is a decimal number followed by the `e` of an
+/// exponent.
+fn awaits_exponent(literal: &str) -> bool {
+    match literal.strip_suffix(|c| c == 'e' || c == 'E') {
+        Some(mantissa) => {
+            !mantissa.is_empty()
+                && mantissa
+                    .chars()
+                    .all(|c| c.is_ascii_digit() || c == '.' || c == '_')
+        }
+        None => false,
+    }
+}
+
+/// Parses a numeric literal, which may have an exponent as in `5e3` and be
+/// directly followed by a unit as in `5km`.
+fn number(literal: &str) -> Result<Vec<Token>, Error> {
+    let literal = literal.replace('_', "").to_ascii_lowercase();
+    let radix = match literal.get(..2) {
+        Some("0x") => Some(16),
+        Some("0o") => Some(8),
+        Some("0b") => Some(2),
+        _ => None,
+    };
+    if let Some(radix) = radix {
+        return i64::from_str_radix(&literal[2..], radix)
+            .map(|n| vec![Token::Number(n as f64)])
+            .map_err(|_| Error::Syntax);
+    }
+
+    let mut split = literal
+        .find(|c: char| c.is_ascii_alphabetic())
+        .unwrap_or(literal.len());
+    if let Some(exponent) = literal[split..].strip_prefix('e') {
+        let unsigned = exponent
+            .strip_prefix(|c| c == '+' || c == '-')
+            .unwrap_or(exponent);
+        let digits = unsigned.len()
+            - unsigned
+                .trim_start_matches(|c: char| c.is_ascii_digit())
+                .len();
+        // otherwise the `e` starts a unit
+        if digits > 0 {
+            split = literal.len() - unsigned.len() + digits;
+        }
+    }
+    let (digits, suffix) = literal.split_at(split);
+    let n = digits.parse::<f64>().map_err(|_| Error::Syntax)?;
+    let mut tokens = vec![Token::Number(n)];
+    if !suffix.is_empty() {
+        tokens.push(Token::Ident(suffix.to_lowercase()));
+    }
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Dimension {
+    Length,
+    Mass,
+    Time,
+    Data,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Unit {
+    symbol: &'static str,
+    dimension: Dimension,
+    /// Size of the unit in the base unit of its dimension.
+    factor: f64,
+}
+
+#[rustfmt::skip]
+const UNITS: &[(&[&str], Unit)] = {
+    const fn unit(symbol: &'static str, dimension: Dimension, factor: f64) -> Unit {
+        Unit {
+            symbol,
+            dimension,
+            factor,
+        }
+    }
+    use Dimension::*;
+    &[
+        (&["mm", "millimeter", "millimeters"], unit("mm", Length, 0.001)),
+        (&["cm", "centimeter", "centimeters"], unit("cm", Length, 0.01)),
+        (&["m", "meter", "meters"], unit("m", Length, 1.0)),
+        (&["km", "kilometer", "kilometers"], unit("km", Length, 1000.0)),
+        (&["in", "inch", "inches"], unit("in", Length, 0.0254)),
+        (&["ft", "foot", "feet"], unit("ft", Length, 0.3048)),
+        (&["yd", "yard", "yards"], unit("yd", Length, 0.9144)),
+        (&["mi", "mile", "miles"], unit("mi", Length, 1609.344)),
+        (&["mg", "milligram", "milligrams"], unit("mg", Mass, 0.001)),
+        (&["g", "gram", "grams"], unit("g", Mass, 1.0)),
+        (&["kg", "kilogram", "kilograms"], unit("kg", Mass, 1000.0)),
+        (&["t", "tonne", "tonnes"], unit("t", Mass, 1_000_000.0)),
+        (&["oz", "ounce", "ounces"], unit("oz", Mass, 28.349_523_125)),
+        (&["lb", "lbs", "pound", "pounds"], unit("lb", Mass, 453.592_37)),
+        (&["ms", "millisecond", "milliseconds"], unit("ms", Time, 0.001)),
+        (&["s", "sec", "second", "seconds"], unit("s", Time, 1.0)),
+        (&["min", "minute", "minutes"], unit("min", Time, 60.0)),
+        (&["h", "hr", "hour", "hours"], unit("h", Time, 3600.0)),
+        (&["d", "day", "days"], unit("d", Time, 86400.0)),
+        (&["wk", "week", "weeks"], unit("wk", Time, 604_800.0)),
+        (&["b", "byte", "bytes"], unit("B", Data, 1.0)),
+        (&["kb"], unit("kB", Data, 1e3)),
+        (&["mb"], unit("MB", Data, 1e6)),
+        (&["gb"], unit("GB", Data, 1e9)),
+        (&["tb"], unit("TB", Data, 1e12)),
+        (&["kib"], unit("KiB", Data, 1024.0)),
+        (&["mib"], unit("MiB", Data, 1_048_576.0)),
+        (&["gib"], unit("GiB", Data, 1_073_741_824.0)),
+        (&["tib"], unit("TiB", Data, 1_099_511_627_776.0)),
+    ]
+};
+
+fn unit(ident: &str) -> Option<Unit> {
+    UNITS
+        .iter()
+        .find(|(names, _)| names.contains(&ident))
+        .map(|(_, unit)| *unit)
+}
+
+/// A number with an optional unit.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Value {
+    n: f64,
+    unit: Option<Unit>,
+    /// Written as a percentage, which is relative to the left operand of
+    /// an addition or subtraction.
+    percent: bool,
+}
+
+impl Value {
+    fn scalar(n: f64) -> Self {
+        Self {
+            n,
+            unit: None,
+            percent: false,
+        }
+    }
+
+    fn convert(self, to: Unit) -> Result<f64, Error> {
+        match self.unit {
+            Some(from) if from.dimension == to.dimension => Ok(self.n * from.factor / to.factor),
+            _ => Err(Error::IncompatibleUnits),
+        }
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    /// Set once an operator, function or conversion was applied.
+    computed: bool,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat_op(&mut self, op: char) -> bool {
+        if self.peek() == Some(&Token::Op(op)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Whether the tokens at `i` are `to`/`in` followed by a unit or format.
+    fn is_conversion(&self, i: usize) -> bool {
+        let keyword = matches!(self.tokens.get(i), Some(Token::Ident(k)) if k == "to" || k == "in");
+        let target = match self.tokens.get(i + 1) {
+            Some(Token::Ident(target)) => unit(target).is_some() || FORMATS.contains(&&**target),
+            _ => false,
+        };
+        keyword && target
+    }
+
+    /// `sum := product (("+" | "-") product)*`
+    fn expression(&mut self) -> Result<Value, Error> {
+        let mut lhs = self.product()?;
+        loop {
+            let sign = if self.eat_op('+') {
+                1.0
+            } else if self.eat_op('-') {
+                -1.0
+            } else {
+                return Ok(lhs);
+            };
+            let rhs = self.product()?;
+            self.computed = true;
+            let n = if rhs.percent {
+                lhs.n * rhs.n
+            } else {
+                match (lhs.unit, rhs.unit) {
+                    (Some(unit), Some(_)) => rhs.convert(unit)?,
+                    (None, None) => rhs.n,
+                    // a bare number added to a quantity is in the same unit
+                    (Some(_), None) => rhs.n,
+                    (None, Some(_)) => {
+                        lhs.unit = rhs.unit;
+                        rhs.n
+                    }
+                }
+            };
+            lhs.n += sign * n;
+            lhs.percent = false;
+        }
+    }
+
+    /// `product := unary (("*" | "/" | "mod") unary)*`
+    fn product(&mut self) -> Result<Value, Error> {
+        let mut lhs = self.unary()?;
+        loop {
+            let op = if self.eat_op('*') {
+                '*'
+            } else if self.eat_op('/') {
+                '/'
+            } else if matches!(self.peek(), Some(Token::Ident(k)) if k == "mod") {
+                self.pos += 1;
+                '%'
+            } else {
+                return Ok(lhs);
+            };
+            let rhs = self.unary()?;
+            self.computed = true;
+            lhs = match (op, lhs.unit, rhs.unit) {
+                ('/', Some(a), Some(b)) if a.dimension == b.dimension => {
+                    Value::scalar(lhs.n * a.factor / (rhs.n * b.factor))
+                }
+                (_, Some(_), Some(_)) => return Err(Error::IncompatibleUnits),
+                ('*', _, _) => Value {
+                    n: lhs.n * rhs.n,
+                    unit: lhs.unit.or(rhs.unit),
+                    percent: false,
+                },
+                ('/', _, None) => Value {
+                    n: lhs.n / rhs.n,
+                    unit: lhs.unit,
+                    percent: false,
+                },
+                ('%', _, None) => Value {
+                    n: lhs.n % rhs.n,
+                    unit: lhs.unit,
+                    percent: false,
+                },
+                _ => return Err(Error::IncompatibleUnits),
+            };
+        }
+    }
+
+    /// `unary := ("-" | "+") unary | power`
+    fn unary(&mut self) -> Result<Value, Error> {
+        if self.eat_op('-') {
+            let mut value = self.unary()?;
+            value.n = -value.n;
+            Ok(value)
+        } else if self.eat_op('+') {
+            self.unary()
+        } else {
+            self.power()
+        }
+    }
+
+    /// `power := postfix ("^" unary)?`, right associative
+    fn power(&mut self) -> Result<Value, Error> {
+        let base = self.postfix()?;
+        if self.eat_op('^') {
+            let exponent = self.unary()?;
+            self.computed = true;
+            if base.unit.is_some() || exponent.unit.is_some() {
+                return Err(Error::IncompatibleUnits);
+            }
+            Ok(Value::scalar(base.n.powf(exponent.n)))
+        } else {
+            Ok(base)
+        }
+    }
+
+    /// `postfix := primary "%"? unit?`
+    fn postfix(&mut self) -> Result<Value, Error> {
+        let mut value = self.primary()?;
+        if self.eat_op('%') {
+            value.n /= 100.0;
+            value.percent = true;
+        }
+        if let Some(Token::Ident(ident)) = self.peek() {
+            if let Some(unit) = unit(ident) {
+                // `in` is a unit unless it starts a conversion
+                if value.unit.is_none() && !self.is_conversion(self.pos) {
+                    value.unit = Some(unit);
+                    self.pos += 1;
+                }
+            }
+        }
+        Ok(value)
+    }
+
+    /// `primary := number | constant | function "(" args ")" | "(" sum ")"`
+    fn primary(&mut self) -> Result<Value, Error> {
+        match self.next() {
+            Some(Token::Number(n)) => Ok(Value::scalar(n)),
+            Some(Token::Op('(')) => {
+                let value = self.expression()?;
+                if self.eat_op(')') {
+                    Ok(value)
+                } else {
+                    Err(Error::Syntax)
+                }
+            }
+            Some(Token::Ident(ident)) => match ident.as_str() {
+                "pi" | "π" => Ok(Value::scalar(consts::PI)),
+                "e" => Ok(Value::scalar(consts::E)),
+                "tau" | "τ" => Ok(Value::scalar(consts::TAU)),
+                _ => self.function(ident),
+            },
+            _ => Err(Error::Syntax),
+        }
+    }
+
+    fn function(&mut self, name: String) -> Result<Value, Error> {
+        let (arity, f): (usize, fn(&[f64]) -> f64) = match name.as_str() {
+            "sqrt" => (1, |a| a[0].sqrt()),
+            "cbrt" => (1, |a| a[0].cbrt()),
+            "abs" => (1, |a| a[0].abs()),
+            "round" => (1, |a| a[0].round()),
+            "floor" => (1, |a| a[0].floor()),
+            "ceil" => (1, |a| a[0].ceil()),
+            "exp" => (1, |a| a[0].exp()),
+            "ln" => (1, |a| a[0].ln()),
+            "log" => (1, |a| a[0].log10()),
+            "log2" => (1, |a| a[0].log2()),
+            "sin" => (1, |a| a[0].sin()),
+            "cos" => (1, |a| a[0].cos()),
+            "tan" => (1, |a| a[0].tan()),
+            "asin" => (1, |a| a[0].asin()),
+            "acos" => (1, |a| a[0].acos()),
+            "atan" => (1, |a| a[0].atan()),
+            "min" => (2, |a| a[0].min(a[1])),
+            "max" => (2, |a| a[0].max(a[1])),
+            "pow" => (2, |a| a[0].powf(a[1])),
+            _ => return Err(Error::UnknownIdentifier),
+        };
+
+        if !self.eat_op('(') {
+            return Err(Error::Syntax);
+        }
+        let mut args = Vec::with_capacity(arity);
+        if !self.eat_op(')') {
+            loop {
+                let arg = self.expression()?;
+                if arg.unit.is_some() {
+                    return Err(Error::IncompatibleUnits);
+                }
+                args.push(arg.n);
+                if self.eat_op(')') {
+                    break;
+                }
+                if !self.eat_op(',') {
+                    return Err(Error::Syntax);
+                }
+            }
+        }
+        if args.len() != arity {
+            return Err(Error::Arity);
+        }
+        self.computed = true;
+        Ok(Value::scalar(f(&args)))
+    }
+
+    /// Applies a trailing `to <unit|format>` and formats the value.
+    fn conversion(&mut self, value: Value) -> Result<String, Error> {
+        if !value.n.is_finite() {
+            return Err(Error::NotFinite);
+        }
+        if !self.is_conversion(self.pos) {
+            return Ok(match value.unit {
+                Some(unit) => format!("{} {}", format(value.n), unit.symbol),
+                None => format(value.n),
+            });
+        }
+
+        self.pos += 1;
+        self.computed = true;
+        let target = match self.next() {
+            Some(Token::Ident(target)) => target,
+            _ => return Err(Error::Syntax),
+        };
+        if let Some(to) = unit(&target) {
+            return Ok(format!("{} {}", format(value.convert(to)?), to.symbol));
+        }
+
+        if value.n.fract() != 0.0 || value.n.abs() > i64::MAX as f64 {
+            return Err(Error::NotAnInteger);
+        }
+        let n = value.n as i64;
+        let sign = if n < 0 { "-" } else { "" };
+        Ok(match target.as_str() {
+            "hex" => format!("{sign}0x{:x}", n.unsigned_abs()),
+            "oct" => format!("{sign}0o{:o}", n.unsigned_abs()),
+            "bin" => format!("{sign}0b{:b}", n.unsigned_abs()),
+            _ => format!("{n}"),
+        })
+    }
+}
+
+const FORMATS: &[&str] = &["hex", "oct", "bin", "dec"];
+
+/// Formats `n` with up to 10 decimals, without trailing zeros.
+fn format(n: f64) -> String {
+    if n.fract() == 0.0 && n.abs() < 1e15 {
+        return format!("{}", n as i64);
+    }
+    if n.abs() >= 1e15 || n.abs() < 1e-6 {
+        return format!("{n:e}");
+    }
+    let s = format!("{n:.10}");
+    s.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(input: &str) -> String {
+        evaluate(input).unwrap_or_else(|err| panic!("{input:?} failed with {err:?}"))
+    }
+
+    #[test]
+    fn precedence() {
+        assert_eq!(eval("2 + 3 * 4"), "14");
+        assert_eq!(eval("(2 + 3) * 4"), "20");
+        assert_eq!(eval("2 * 3 ^ 2"), "18");
+        assert_eq!(eval("10 - 6 / 2"), "7");
+        assert_eq!(eval("2 + 7 mod 3"), "3");
+        assert_eq!(eval("((1 + 2) * (3 + 4))"), "21");
+    }
+
+    #[test]
+    fn associativity() {
+        assert_eq!(eval("10 - 4 - 3"), "3");
+        assert_eq!(eval("100 / 10 / 5"), "2");
+        assert_eq!(eval("2 ^ 3 ^ 2"), "512");
+        assert_eq!(eval("17 mod 7 mod 2"), "1");
+    }
+
+    #[test]
+    fn unary_minus() {
+        assert_eq!(eval("-2 + 5"), "3");
+        assert_eq!(eval("-2 ^ 2"), "-4");
+        assert_eq!(eval("(-2) ^ 2"), "4");
+        assert_eq!(eval("2 ^ -1"), "0.5");
+        assert_eq!(eval("3 - -2"), "5");
+        assert_eq!(eval("--3 * 2"), "6");
+        assert_eq!(eval("+4 - 1"), "3");
+    }
+
+    #[test]
+    fn numbers() {
+        assert_eq!(eval("1.5 * 2"), "3");
+        assert_eq!(eval(".5 + 1"), "1.5");
+        assert_eq!(eval("1_000 * 2"), "2000");
+        assert_eq!(eval("5e3 + 1"), "5001");
+        assert_eq!(eval("1.5E-3 * 2"), "0.003");
+        assert_eq!(eval("2e+2 / 4"), "50");
+        assert_eq!(eval("1 / 3"), "0.3333333333");
+        assert_eq!(eval("2 ^ 64"), "1.8446744073709552e19");
+        assert_eq!(eval("3 × 4 ÷ 2"), "6");
+    }
+
+    #[test]
+    fn functions_and_constants() {
+        assert_eq!(eval("sqrt(16) + 1"), "5");
+        assert_eq!(eval("max(2, 3) * 2"), "6");
+        assert_eq!(eval("min(2, -3)"), "-3");
+        assert_eq!(eval("pow(2, 10)"), "1024");
+        assert_eq!(eval("abs(-7)"), "7");
+        assert_eq!(eval("sin(0)"), "0");
+        assert_eq!(eval("2 * pi"), "6.2831853072");
+        assert_eq!(eval("ln(e)"), "1");
+        assert_eq!(eval("SQRT(9)"), "3");
+        assert_eq!(evaluate("min(1)"), Err(Error::Arity));
+        assert_eq!(evaluate("foo(1)"), Err(Error::UnknownIdentifier));
+        assert_eq!(evaluate("sqrt 4"), Err(Error::Syntax));
+        assert_eq!(evaluate("sqrt(4"), Err(Error::Syntax));
+    }
+
+    #[test]
+    fn units() {
+        assert_eq!(eval("5 km to mi"), "3.1068559612 mi");
+        assert_eq!(eval("5km in m"), "5000 m");
+        assert_eq!(eval("1 km + 500 m"), "1.5 km");
+        assert_eq!(eval("2 h to min"), "120 min");
+        assert_eq!(eval("1 in to cm"), "2.54 cm");
+        assert_eq!(eval("1 GiB to MB"), "1073.741824 MB");
+        assert_eq!(eval("3 kg * 2"), "6 kg");
+        assert_eq!(eval("2e3 m to km"), "2 km");
+        assert_eq!(eval("1 km / 250 m"), "4");
+        assert_eq!(evaluate("3 kg + 2 s"), Err(Error::IncompatibleUnits));
+        assert_eq!(evaluate("5 km to kg"), Err(Error::IncompatibleUnits));
+        assert_eq!(evaluate("2 ^ 3 m"), Err(Error::IncompatibleUnits));
+    }
+
+    #[test]
+    fn bases() {
+        assert_eq!(eval("0xff + 1"), "256");
+        assert_eq!(eval("0XFF + 1"), "256");
+        assert_eq!(eval("0b101 * 2"), "10");
+        assert_eq!(eval("0o17 + 1"), "16");
+        assert_eq!(eval("0x1e+5"), "35");
+        assert_eq!(eval("255 to hex"), "0xff");
+        assert_eq!(eval("10 to bin"), "0b1010");
+        assert_eq!(eval("8 to oct"), "0o10");
+        assert_eq!(eval("-10 to hex"), "-0xa");
+        assert_eq!(eval("0xff to dec"), "255");
+        assert_eq!(evaluate("1.5 to hex"), Err(Error::NotAnInteger));
+        assert_eq!(evaluate("0xfg + 1"), Err(Error::Syntax));
+    }
+
+    #[test]
+    fn percentages() {
+        assert_eq!(eval("200 + 10%"), "220");
+        assert_eq!(eval("200 - 10%"), "180");
+        assert_eq!(eval("50% * 10"), "5");
+    }
+
+    #[test]
+    fn not_expressions() {
+        for input in [
+            "", "  ", "42", "-5", "+5", "5 km", "-5 km", "0xff", "5e3", "pi", "50%",
+        ] {
+            assert_eq!(evaluate(input), Err(Error::NotAnExpression), "{input:?}");
+        }
+        assert_eq!(evaluate("firefox"), Err(Error::UnknownIdentifier));
+        assert_eq!(evaluate("5 apples"), Err(Error::Syntax));
+        assert_eq!(evaluate("2 +"), Err(Error::Syntax));
+        assert_eq!(evaluate("1 / 0"), Err(Error::NotFinite));
+        assert_eq!(evaluate("$5"), Err(Error::Syntax));
+    }
+}
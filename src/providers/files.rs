@@ -0,0 +1,420 @@
+//! Files and directories, searched by starting the query with `~`, below
+//! the configured roots, or with `/`, below the directory the query names.
+//!
+//! The directories are walked in the background, skipping hidden files and
+//! those ignored by `.gitignore` and `.ignore` files, and searches look at
+//! the names indexed so far while the walk goes on. Once the index got
+//! stale, the directories modified since are read again while it is still
+//! used, and the updated index replaces it.
+
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use futures::stream::{self, BoxStream, StreamExt};
+use ignore::WalkBuilder;
+use pop_launcher::{IconSource, SearchResult};
+
+use super::{home_relative, ready, Action, Provider, ProviderResult};
+use crate::{fuzzy, uri};
+
+const MAX_RESULTS: usize = 20;
+/// How long after the walk or the last update the index is used before the
+/// modified directories are read again.
+const STALE_AFTER: Duration = Duration::from_secs(5 * 60);
+/// How often a running search looks at the names indexed since.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+/// Paths added to the index at once while walking, and matched at once
+/// while searching.
+const BATCH_SIZE: usize = 512;
+/// Virtual file systems, left out when searching below `/`.
+const SKIPPED: &[&str] = &["/dev", "/proc", "/run", "/sys"];
+
+#[derive(Default)]
+struct Index {
+    paths: Mutex<Vec<PathBuf>>,
+    /// Modification times of the walked directories, which tell the ones
+    /// to read again.
+    dirs: Mutex<HashMap<PathBuf, SystemTime>>,
+    /// When the walk or the last update finished.
+    finished: Mutex<Option<Instant>>,
+    /// Set while the modified directories are read for an updated index.
+    updating: AtomicBool,
+    /// Set once the index is dropped, which stops its walk and update.
+    cancelled: AtomicBool,
+}
+
+impl Index {
+    fn is_stale(&self) -> bool {
+        self.finished
+            .lock()
+            .unwrap()
+            .map_or(false, |finished| finished.elapsed() > STALE_AFTER)
+    }
+}
+
+/// Directories walked into an index.
+struct Tree {
+    roots: Vec<PathBuf>,
+    /// Replaced by the update once the index got stale.
+    index: Arc<Mutex<Option<Arc<Index>>>>,
+}
+
+impl Tree {
+    fn new(roots: Vec<PathBuf>) -> Self {
+        Self {
+            roots,
+            index: Arc::default(),
+        }
+    }
+
+    /// The index, walking the roots on the first search, and updating it in
+    /// the background once it got stale.
+    fn index(&self) -> Arc<Index> {
+        let mut current = self.index.lock().unwrap();
+        let index = match current.as_ref() {
+            Some(index) => index.clone(),
+            None => {
+                let index = Arc::new(Index::default());
+                let (walker, roots) = (index.clone(), self.roots.clone());
+                tokio::task::spawn_blocking(move || walk(&roots, &walker));
+                *current = Some(index.clone());
+                return index;
+            }
+        };
+        if index.is_stale() && !index.updating.swap(true, Ordering::Relaxed) {
+            let (slot, stale) = (self.index.clone(), index.clone());
+            tokio::task::spawn_blocking(move || match update(&stale) {
+                Some(updated) if !stale.cancelled.load(Ordering::Relaxed) => {
+                    *slot.lock().unwrap() = Some(Arc::new(updated));
+                }
+                _ => {
+                    *stale.finished.lock().unwrap() = Some(Instant::now());
+                    stale.updating.store(false, Ordering::Relaxed);
+                }
+            });
+        }
+        index
+    }
+}
+
+impl Drop for Tree {
+    fn drop(&mut self) {
+        if let Some(index) = self.index.lock().unwrap().as_ref() {
+            index.cancelled.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+pub struct Files {
+    /// Below the configured roots.
+    home: Tree,
+    /// Below the directory named by the current `/` query.
+    absolute: Option<Tree>,
+}
+
+impl Files {
+    /// Searches below `roots`, where a leading `~` stands for the home
+    /// directory.
+    pub fn new(roots: &[String]) -> Self {
+        let home = std::env::var_os("HOME").map(PathBuf::from);
+        let roots = roots
+            .iter()
+            .filter_map(|root| match root.strip_prefix('~') {
+                Some(rest) => Some(home.as_ref()?.join(rest.trim_start_matches('/'))),
+                None => Some(PathBuf::from(root)),
+            })
+            .collect();
+        Self {
+            home: Tree::new(roots),
+            absolute: None,
+        }
+    }
+}
+
+impl Provider for Files {
+    fn name(&self) -> &'static str {
+        "files"
+    }
+
+    fn search_prefixed(&mut self, query: &str) -> BoxStream<'static, Vec<ProviderResult>> {
+        self.search(&format!("~{query}"))
+    }
+
+    fn search(&mut self, query: &str) -> BoxStream<'static, Vec<ProviderResult>> {
+        let (index, pattern) = if let Some(pattern) = query.strip_prefix('~') {
+            self.absolute = None;
+            let pattern = pattern.trim_start_matches('/').trim();
+            // everything below the roots would match
+            if pattern.is_empty() {
+                return ready(Vec::new());
+            }
+            (self.home.index(), pattern.to_string())
+        } else if query.starts_with('/') {
+            let (dir, pattern) = split_absolute(query.trim_end());
+            let tree = match self.absolute.take() {
+                Some(tree) if tree.roots == [dir.clone()] => tree,
+                _ => Tree::new(vec![dir]),
+            };
+            (self.absolute.insert(tree).index(), pattern)
+        } else {
+            // the walk below the last directory is not needed anymore
+            self.absolute = None;
+            return ready(Vec::new());
+        };
+
+        // the stream is dropped along with its search when the query
+        // changes, while the walk goes on for the next one
+        let search = Search {
+            index,
+            pattern,
+            scanned: 0,
+            best: Vec::new(),
+            sent: Vec::new(),
+        };
+        stream::unfold(search, |mut search| async move {
+            loop {
+                // checked before scanning so the last names are not missed
+                let finished = search.index.finished.lock().unwrap().is_some();
+                if !search.scan() {
+                    tokio::task::yield_now().await;
+                    continue;
+                }
+                if let Some(results) = search.unsent() {
+                    return Some((results, search));
+                }
+                if finished {
+                    return None;
+                }
+                tokio::time::sleep(POLL_INTERVAL).await;
+            }
+        })
+        .boxed()
+    }
+}
+
+/// A search through an index while it is walked.
+struct Search {
+    index: Arc<Index>,
+    pattern: String,
+    /// Indexed paths looked at so far.
+    scanned: usize,
+    /// The best matches so far, along with their rank.
+    best: Vec<((u8, usize, usize), PathBuf)>,
+    /// The matches last sent as results.
+    sent: Vec<PathBuf>,
+}
+
+impl Search {
+    /// Matches the next batch of indexed paths, and returns whether all of
+    /// them have been looked at.
+    fn scan(&mut self) -> bool {
+        // copied out so the walk is not held up while matching
+        let (batch, all) = {
+            let paths = self.index.paths.lock().unwrap();
+            let end = paths.len().min(self.scanned + BATCH_SIZE);
+            (paths[self.scanned..end].to_vec(), end == paths.len())
+        };
+        self.scanned += batch.len();
+        let matches = batch
+            .into_iter()
+            .filter(|path| is_match(&self.pattern, path))
+            .map(|path| (rank(&self.pattern, &path), path));
+        self.best.extend(matches);
+        self.best.sort();
+        self.best.truncate(MAX_RESULTS);
+        all
+    }
+
+    /// The results for the best matches, unless they were sent already.
+    fn unsent(&mut self) -> Option<Vec<ProviderResult>> {
+        let best: Vec<PathBuf> = self.best.iter().map(|(_, path)| path.clone()).collect();
+        if best == self.sent {
+            return None;
+        }
+        let results = best.iter().map(|path| result(path)).collect();
+        self.sent = best;
+        Some(results)
+    }
+}
+
+/// The deepest existing directory that an absolute `query` starts with,
+/// and the rest of the query, which is searched below it.
+fn split_absolute(query: &str) -> (PathBuf, String) {
+    // the last component is searched for even if it names a directory
+    let parent = query.rsplit_once('/').map_or("", |(parent, _)| parent);
+    let dir = Path::new(parent)
+        .ancestors()
+        .find(|dir| dir.is_dir())
+        .unwrap_or_else(|| Path::new("/"));
+    let rest = &query[dir.as_os_str().len().max(1)..];
+    (dir.to_path_buf(), rest.trim_matches('/').to_string())
+}
+
+/// Adds the paths below `roots` to `index` until it is cancelled.
+fn walk(roots: &[PathBuf], index: &Index) {
+    if let Some(builder) = walker(roots) {
+        if add(&builder, index, &index.cancelled).is_none() {
+            return;
+        }
+    }
+    *index.finished.lock().unwrap() = Some(Instant::now());
+}
+
+/// A copy of `index` with the entries of the directories modified since
+/// they were walked read again, or `None` if none were or the update was
+/// cancelled.
+fn update(index: &Index) -> Option<Index> {
+    let walked = index.dirs.lock().unwrap().clone();
+    let changed: HashSet<PathBuf> = walked
+        .iter()
+        .filter(|(dir, time)| modified(dir) != Some(**time))
+        .map(|(dir, _)| dir.clone())
+        .collect();
+    if changed.is_empty() {
+        return None;
+    }
+
+    // removed directories are modified as well, along with their parent
+    let mut paths = index.paths.lock().unwrap().clone();
+    paths.retain(|path| path.parent().map_or(true, |dir| !changed.contains(dir)));
+    let mut dirs = walked.clone();
+    dirs.retain(|dir, _| !changed.contains(dir));
+    let updated = Index {
+        paths: Mutex::new(paths),
+        dirs: Mutex::new(dirs),
+        ..Index::default()
+    };
+    for dir in changed.into_iter().filter(|dir| dir.is_dir()) {
+        let mut entries = walker(&[dir])?;
+        entries.max_depth(Some(1));
+        // new directories are walked whole, the others are kept as they were
+        let new: Vec<PathBuf> = add(&entries, &updated, &index.cancelled)?
+            .into_iter()
+            .filter(|dir| !walked.contains_key(dir))
+            .collect();
+        if let Some(builder) = walker(&new) {
+            add(&builder, &updated, &index.cancelled)?;
+        }
+    }
+    *updated.finished.lock().unwrap() = Some(Instant::now());
+    Some(updated)
+}
+
+fn walker(roots: &[PathBuf]) -> Option<WalkBuilder> {
+    let (first, rest) = roots.split_first()?;
+    let mut builder = WalkBuilder::new(first);
+    for root in rest {
+        builder.add(root);
+    }
+    builder.filter_entry(|entry| !SKIPPED.iter().any(|dir| entry.path() == Path::new(dir)));
+    Some(builder)
+}
+
+/// Adds the paths walked below the roots of `walker` to `index`, along with
+/// the modification times of the directories, and returns the directories
+/// walked into. Returns `None` once `cancelled` is set.
+fn add(walker: &WalkBuilder, index: &Index, cancelled: &AtomicBool) -> Option<Vec<PathBuf>> {
+    let mut dirs = Vec::new();
+    let mut batch = Vec::with_capacity(BATCH_SIZE);
+    for entry in walker.build() {
+        if cancelled.load(Ordering::Relaxed) {
+            return None;
+        }
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(err) => {
+                log::debug!("skipped while indexing files: {err}");
+                continue;
+            }
+        };
+        if entry.file_type().map_or(false, |t| t.is_dir()) {
+            if let Some(time) = modified(entry.path()) {
+                let path = entry.path().to_path_buf();
+                index.dirs.lock().unwrap().insert(path, time);
+            }
+            if entry.depth() > 0 {
+                dirs.push(entry.path().to_path_buf());
+            }
+        }
+        // the roots themselves are not listed
+        if entry.depth() > 0 {
+            batch.push(entry.into_path());
+        }
+        if batch.len() == BATCH_SIZE {
+            index.paths.lock().unwrap().append(&mut batch);
+        }
+    }
+    index.paths.lock().unwrap().append(&mut batch);
+    Some(dirs)
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Whether the file name matches `pattern`, or the whole path if the
+/// pattern has several components.
+fn is_match(pattern: &str, path: &Path) -> bool {
+    if pattern.contains('/') {
+        return fuzzy::is_match(pattern, &home_relative(path));
+    }
+    path.file_name().map_or(false, |name| {
+        fuzzy::is_match(pattern, &name.to_string_lossy())
+    })
+}
+
+/// Ranks names equal to `pattern` first, then those starting with it, then
+/// those containing it, and then the other matches, the shallower and
+/// shorter paths first among them.
+fn rank(pattern: &str, path: &Path) -> (u8, usize, usize) {
+    let name = if pattern.contains('/') {
+        home_relative(path)
+    } else {
+        path.file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    };
+    let (name, pattern) = (name.to_lowercase(), pattern.to_lowercase());
+    let quality = match name.find(&pattern) {
+        Some(_) if name == pattern => 0,
+        Some(0) => 1,
+        Some(_) => 2,
+        None => 3,
+    };
+    (quality, path.components().count(), name.len())
+}
+
+fn result(path: &Path) -> ProviderResult {
+    let mime = if path.is_dir() {
+        "inode/directory".to_string()
+    } else {
+        mime_guess::from_path(path)
+            .first_or_octet_stream()
+            .essence_str()
+            .to_string()
+    };
+    ProviderResult {
+        result: SearchResult {
+            id: 0,
+            name: path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            description: path.parent().map(home_relative).unwrap_or_default(),
+            icon: Some(IconSource::Mime(Cow::Owned(mime))),
+            category_icon: Some(IconSource::Name(Cow::Borrowed("system-file-manager"))),
+            window: None,
+        },
+        action: Action::Open {
+            uri: uri::from_path(path),
+            app: None,
+        },
+        options: Vec::new(),
+        details: Vec::new(),
+    }
+}
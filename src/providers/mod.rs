@@ -0,0 +1,142 @@
+//! In-process result providers, queried alongside the pop-launcher service.
+
+use std::path::{Path, PathBuf};
+
+use futures::{
+    future,
+    stream::{self, BoxStream},
+    StreamExt,
+};
+use pop_launcher::SearchResult;
+
+use crate::preferences::Preferences;
+use crate::uri;
+
+pub mod calculator;
+pub mod files;
+pub mod recent;
+pub mod scripts;
+pub mod ssh;
+pub mod web;
+
+/// Names of the local providers, enabled or not, which prefixes may target.
+pub const NAMES: &[&str] = &["web", "calculator", "recent", "ssh", "files", "scripts"];
+
+/// Where a result came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Origin {
+    /// The pop-launcher service.
+    Service,
+    /// A local provider, by its position in the aggregator, along with the
+    /// search its results belong to.
+    Local { provider: usize, generation: u64 },
+}
+
+/// What the frontend should do when a local result is activated.
+#[derive(Debug, Clone)]
+pub enum Action {
+    /// Replace the query with this text.
+    Fill(String),
+    /// Copy this text to the clipboard and close the launcher.
+    Copy(String),
+    /// Open a URI with the desktop entry at `app`, or with the default
+    /// application for its type, and close the launcher.
+    Open { uri: String, app: Option<PathBuf> },
+    /// Run a program with its arguments in a terminal and close the
+    /// launcher.
+    Terminal(Vec<String>),
+    /// Call a user script back with the id of its activated result, and
+    /// carry out the action it answers with.
+    Callback { script: PathBuf, id: String },
+}
+
+/// A result of a local provider along with what activating it does.
+///
+/// The id of `result` is assigned by the aggregator.
+#[derive(Debug, Clone)]
+pub struct ProviderResult {
+    pub result: SearchResult,
+    pub action: Action,
+    /// Further actions offered as context options, along with their name.
+    pub options: Vec<(String, Action)>,
+    /// Further information shown in the preview pane, as labels and values.
+    pub details: Vec<(String, String)>,
+}
+
+pub trait Provider: Send {
+    /// Short name used in logs and as the target of query prefixes.
+    fn name(&self) -> &'static str;
+
+    /// Searches `query`.
+    ///
+    /// Every item of the stream replaces the previous results of this
+    /// provider. The stream is dropped as soon as the query changes, so
+    /// long-running searches should stop once their receiver is gone.
+    fn search(&mut self, query: &str) -> BoxStream<'static, Vec<ProviderResult>>;
+
+    /// Searches `query` typed after a prefix of this provider, which is then
+    /// the only one searched. Defaults to [`Provider::search`], for
+    /// providers that need no keyword of their own.
+    fn search_prefixed(&mut self, query: &str) -> BoxStream<'static, Vec<ProviderResult>> {
+        self.search(query)
+    }
+
+    /// Called when one of the results of the last search is activated.
+    fn activate(&mut self, result: &ProviderResult) -> Option<Action> {
+        Some(result.action.clone())
+    }
+
+    /// The text copied to the clipboard for one of the results of the last
+    /// search. Defaults to the value a result copies when activated, the
+    /// path of a file it opens, and to its name otherwise.
+    fn copy(&self, result: &ProviderResult) -> String {
+        match &result.action {
+            Action::Copy(text) => text.clone(),
+            Action::Open { uri, .. } => uri::to_path(uri)
+                .map(|path| path.to_string_lossy().into_owned())
+                .unwrap_or_else(|| uri.clone()),
+            _ => result.result.name.clone(),
+        }
+    }
+}
+
+/// Wraps results that are known immediately in a stream.
+pub fn ready(results: Vec<ProviderResult>) -> BoxStream<'static, Vec<ProviderResult>> {
+    stream::once(future::ready(results)).boxed()
+}
+
+/// `path` with the home directory shortened to `~`.
+pub fn home_relative(path: &Path) -> String {
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+    match home.as_ref().and_then(|home| path.strip_prefix(home).ok()) {
+        Some(rest) if rest.as_os_str().is_empty() => "~".to_string(),
+        Some(rest) => format!("~/{}", rest.display()),
+        None => path.display().to_string(),
+    }
+}
+
+/// The local providers enabled in the preferences, in the order their
+/// results are listed.
+pub fn enabled(preferences: &Preferences) -> Vec<Box<dyn Provider>> {
+    let mut providers: Vec<Box<dyn Provider>> = Vec::new();
+    // an address or web search is what the whole query asked for
+    if preferences.web_search {
+        providers.push(Box::new(web::Web::new(preferences.search_engines.clone())));
+    }
+    if preferences.calculator {
+        providers.push(Box::new(calculator::Calculator));
+    }
+    if preferences.recent_files {
+        providers.push(Box::<recent::RecentFiles>::default());
+    }
+    if preferences.ssh_hosts {
+        providers.push(Box::<ssh::SshHosts>::default());
+    }
+    if preferences.file_search {
+        providers.push(Box::new(files::Files::new(&preferences.file_search_roots)));
+    }
+    if preferences.scripts {
+        providers.push(Box::new(scripts::Scripts));
+    }
+    providers
+}
@@ -0,0 +1,241 @@
+//! Recently used files, as recorded by applications in
+//! `$XDG_DATA_HOME/recently-used.xbel`.
+//!
+//! The file is not watched. Every search checks its modification time and
+//! reads it again if it changed since the last one; the applications offered
+//! to open the files are read again every few minutes.
+
+use std::borrow::Cow;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use futures::stream::{self, BoxStream, StreamExt};
+use pop_launcher::{IconSource, SearchResult};
+
+use super::{home_relative, ready, Action, Provider, ProviderResult};
+use crate::apps::Apps;
+use crate::localize::requested_languages;
+use crate::mime_apps::Associations;
+use crate::{fl, fuzzy, uri};
+
+const FILE: &str = "recently-used.xbel";
+/// Recent files listed at once, the most recently used first.
+const MAX_RESULTS: usize = 5;
+/// How long the applications are used before they are read again, since
+/// some may have been installed along with the new files.
+const APPS_STALE_AFTER: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Debug, Clone)]
+struct RecentFile {
+    uri: String,
+    name: String,
+    mime: String,
+    /// The application that used the file last.
+    application: Option<String>,
+    /// RFC 3339 time of the last use, which sorts chronologically.
+    modified: String,
+}
+
+#[derive(Default)]
+pub struct RecentFiles {
+    /// Read and searched on a blocking thread, one search at a time.
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Default)]
+struct State {
+    files: Vec<RecentFile>,
+    /// When the file was last read.
+    read_at: Option<SystemTime>,
+    apps: Apps,
+    associations: Associations,
+    /// When the applications were last read, which is kept apart from the
+    /// bookmarks since there may be hundreds of desktop entries.
+    apps_read_at: Option<Instant>,
+}
+
+impl State {
+    fn path() -> Option<PathBuf> {
+        xdg::BaseDirectories::new()
+            .ok()
+            .map(|dirs| dirs.get_data_home().join(FILE))
+    }
+
+    /// Reads the bookmarks again if the file changed, and the applications
+    /// if they got stale.
+    fn refresh(&mut self) {
+        if self
+            .apps_read_at
+            .map_or(true, |read_at| read_at.elapsed() > APPS_STALE_AFTER)
+        {
+            let languages = requested_languages();
+            self.apps = Apps::load(languages.first().map(|id| id.language.as_str()));
+            self.associations = Associations::load();
+            self.apps_read_at = Some(Instant::now());
+        }
+
+        let path = match Self::path() {
+            Some(path) => path,
+            None => return,
+        };
+        let modified = fs::metadata(&path).and_then(|m| m.modified()).ok();
+        if modified.is_some() && modified == self.read_at {
+            return;
+        }
+        self.read_at = modified;
+        self.files = match fs::read_to_string(&path) {
+            Ok(xbel) => parse(&xbel),
+            Err(_) => Vec::new(),
+        };
+        self.files.sort_by(|a, b| b.modified.cmp(&a.modified));
+    }
+
+    fn search(&mut self, query: &str) -> Vec<ProviderResult> {
+        self.refresh();
+        self.files
+            .iter()
+            .filter(|file| fuzzy::is_match(query, &file.name))
+            // files removed since they were used
+            .filter(|file| uri::to_path(&file.uri).map_or(true, |path| path.exists()))
+            .take(MAX_RESULTS)
+            .map(|file| ProviderResult {
+                result: SearchResult {
+                    id: 0,
+                    name: file.name.clone(),
+                    description: description(file),
+                    icon: Some(IconSource::Mime(Cow::Owned(file.mime.clone()))),
+                    category_icon: Some(IconSource::Name(Cow::Borrowed("document-open-recent"))),
+                    window: None,
+                },
+                action: Action::Open {
+                    uri: file.uri.clone(),
+                    app: None,
+                },
+                options: self.options(file),
+                details: Vec::new(),
+            })
+            .collect()
+    }
+
+    /// "Open with" options for the applications handling the type of
+    /// `file`.
+    fn options(&self, file: &RecentFile) -> Vec<(String, Action)> {
+        self.associations
+            .handlers(&self.apps, &file.mime)
+            .into_iter()
+            .map(|app| {
+                let action = Action::Open {
+                    uri: file.uri.clone(),
+                    app: Some(app.path.clone()),
+                };
+                (fl!("open-with", name = app.name.clone()), action)
+            })
+            .collect()
+    }
+}
+
+impl Provider for RecentFiles {
+    fn name(&self) -> &'static str {
+        "recent"
+    }
+
+    fn search(&mut self, query: &str) -> BoxStream<'static, Vec<ProviderResult>> {
+        // recent files would crowd out everything else on an empty query
+        if query.trim().is_empty() {
+            return ready(Vec::new());
+        }
+        let state = self.state.clone();
+        let query = query.to_string();
+        let search = move || state.lock().unwrap().search(&query);
+        stream::once(async move {
+            tokio::task::spawn_blocking(search)
+                .await
+                .unwrap_or_default()
+        })
+        .boxed()
+    }
+}
+
+/// The directory of the file, shortened with `~`, and the application that
+/// used it last.
+fn description(file: &RecentFile) -> String {
+    let dir = uri::to_path(&file.uri)
+        .and_then(|path| path.parent().map(home_relative))
+        .unwrap_or_else(|| file.uri.clone());
+    match file.application.as_ref() {
+        Some(application) => format!("{dir} · {application}"),
+        None => dir,
+    }
+}
+
+/// Reads the bookmarks of an XBEL document.
+///
+/// Only the few elements written by GLib's `GBookmarkFile` are looked at,
+/// so a full XML parser is not needed.
+fn parse(xbel: &str) -> Vec<RecentFile> {
+    let mut files = Vec::new();
+    let mut rest = xbel;
+    while let Some(start) = rest.find("<bookmark ") {
+        let end = rest[start..]
+            .find("</bookmark>")
+            .map_or(rest.len(), |end| start + end);
+        let bookmark = &rest[start..end];
+        rest = &rest[end..];
+
+        let tag = element(bookmark, "<bookmark ");
+        let (uri, modified) = match (attribute(tag, "href"), attribute(tag, "modified")) {
+            (Some(uri), Some(modified)) => (uri, modified),
+            _ => continue,
+        };
+        let mime = attribute(element(bookmark, "<mime:mime-type "), "type")
+            .unwrap_or_else(|| "application/octet-stream".to_string());
+        // the application that registered the file most recently
+        let application = elements(bookmark, "<bookmark:application ")
+            .into_iter()
+            .filter_map(|tag| Some((attribute(tag, "modified")?, attribute(tag, "name")?)))
+            .max()
+            .map(|(_, name)| name);
+
+        files.push(RecentFile {
+            name: uri::file_name(&uri),
+            uri,
+            mime,
+            application,
+            modified,
+        });
+    }
+    files
+}
+
+/// The first tag starting with `start`, without its `<` and `>`.
+fn element<'a>(xml: &'a str, start: &str) -> &'a str {
+    elements(xml, start).first().copied().unwrap_or_default()
+}
+
+fn elements<'a>(xml: &'a str, start: &str) -> Vec<&'a str> {
+    xml.match_indices(start)
+        .filter_map(|(i, _)| {
+            let tag = &xml[i + 1..];
+            tag.find('>').map(|end| &tag[..end])
+        })
+        .collect()
+}
+
+/// The unescaped value of the attribute `name` of `tag`.
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let pattern = format!(" {name}=\"");
+    let start = tag.find(&pattern)? + pattern.len();
+    let len = tag[start..].find('"')?;
+    Some(unescape(&tag[start..start + len]))
+}
+
+fn unescape(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
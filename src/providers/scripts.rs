@@ -0,0 +1,213 @@
+//! Results of the executables in `$XDG_CONFIG_HOME/iced-launcher/scripts/`.
+//!
+//! Every script is run as `script search` with the query on stdin, and
+//! prints one JSON object per result line:
+//!
+//! ```json
+//! {"id": "42", "name": "Name", "description": "More", "icon": "icon-name", "action": {"copy": "text"}}
+//! ```
+//!
+//! Only `name` is required. `action` is one of `{"copy": text}`,
+//! `{"open": uri}`, `{"fill": query}` or `{"terminal": [program, args...]}`,
+//! and `details` lists `[label, value]` pairs shown in the preview pane.
+//! Without one, activating the result runs `script activate` with the `id`
+//! on stdin, which may print one of the actions in turn.
+//!
+//! Scripts taking longer than a second to answer are killed, and so are
+//! those still running when the query changes.
+
+use std::borrow::Cow;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+
+use futures::stream::{BoxStream, FuturesUnordered, StreamExt};
+use pop_launcher::{IconSource, SearchResult};
+use serde::Deserialize;
+use tokio::{io::AsyncWriteExt, process::Command};
+
+use super::{ready, Action, Provider, ProviderResult};
+use crate::preferences::PREFIX;
+
+const DIR: &str = "scripts";
+/// How long a script may take to answer a query.
+const SEARCH_TIMEOUT: Duration = Duration::from_secs(1);
+/// How long a script may take to answer an activation.
+const ACTIVATE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Results taken from each script.
+const MAX_RESULTS: usize = 20;
+
+#[derive(Debug, Deserialize)]
+struct ScriptResult {
+    #[serde(default)]
+    id: String,
+    name: String,
+    #[serde(default)]
+    description: String,
+    icon: Option<String>,
+    action: Option<ScriptAction>,
+    #[serde(default)]
+    details: Vec<(String, String)>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum ScriptAction {
+    Copy(String),
+    Open(String),
+    Fill(String),
+    Terminal(Vec<String>),
+}
+
+impl From<ScriptAction> for Action {
+    fn from(action: ScriptAction) -> Self {
+        match action {
+            ScriptAction::Copy(text) => Action::Copy(text),
+            ScriptAction::Open(uri) => Action::Open { uri, app: None },
+            ScriptAction::Fill(query) => Action::Fill(query),
+            ScriptAction::Terminal(args) => Action::Terminal(args),
+        }
+    }
+}
+
+pub struct Scripts;
+
+impl Provider for Scripts {
+    fn name(&self) -> &'static str {
+        "scripts"
+    }
+
+    fn search(&mut self, query: &str) -> BoxStream<'static, Vec<ProviderResult>> {
+        if query.trim().is_empty() {
+            return ready(Vec::new());
+        }
+        let scripts = scripts();
+        if scripts.is_empty() {
+            return ready(Vec::new());
+        }
+
+        // results are listed in the order of the scripts as they answer
+        let mut answers = vec![Vec::new(); scripts.len()];
+        let searches: FuturesUnordered<_> = scripts
+            .into_iter()
+            .enumerate()
+            .map(|(i, script)| {
+                let query = query.to_string();
+                async move { (i, search(&script, &query).await) }
+            })
+            .collect();
+        searches
+            .map(move |(i, results)| {
+                answers[i] = results;
+                answers.concat()
+            })
+            .boxed()
+    }
+}
+
+/// The executables of the scripts directory, by file name.
+fn scripts() -> Vec<PathBuf> {
+    let dir = match xdg::BaseDirectories::with_prefix(PREFIX) {
+        Ok(dirs) => dirs.get_config_home().join(DIR),
+        Err(_) => return Vec::new(),
+    };
+    let entries = match std::fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+    let mut scripts: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.metadata().map_or(false, |m| {
+                m.is_file() && m.permissions().mode() & 0o111 != 0
+            })
+        })
+        .collect();
+    scripts.sort();
+    scripts
+}
+
+/// Runs `script` with `arg` and `input` on stdin, and returns its output.
+///
+/// The script is killed if it does not exit within `timeout`, or once the
+/// returned future is dropped.
+async fn call(script: &Path, arg: &str, input: &str, timeout: Duration) -> Result<String, String> {
+    let mut child = Command::new(script)
+        .arg(arg)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|err| err.to_string())?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // scripts may not read their input at all
+        let _ = stdin.write_all(format!("{input}\n").as_bytes()).await;
+    }
+    let output = match tokio::time::timeout(timeout, child.wait_with_output()).await {
+        Ok(output) => output.map_err(|err| err.to_string())?,
+        Err(_) => return Err(format!("timed out after {}s", timeout.as_secs())),
+    };
+    if !output.status.success() {
+        return Err(format!("exited with {}", output.status));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// The results of `script` for `query`, nothing if it fails.
+async fn search(script: &Path, query: &str) -> Vec<ProviderResult> {
+    let output = match call(script, "search", query, SEARCH_TIMEOUT).await {
+        Ok(output) => output,
+        Err(err) => {
+            log::warn!("script {} failed to search: {err}", script.display());
+            return Vec::new();
+        }
+    };
+    output
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| match serde_json::from_str::<ScriptResult>(line) {
+            Ok(result) => Some(result),
+            Err(err) => {
+                log::warn!("invalid result of script {}: {err}", script.display());
+                None
+            }
+        })
+        .take(MAX_RESULTS)
+        .map(|result| ProviderResult {
+            result: SearchResult {
+                id: 0,
+                name: result.name,
+                description: result.description,
+                icon: result.icon.map(|icon| IconSource::Name(Cow::Owned(icon))),
+                category_icon: Some(IconSource::Name(Cow::Borrowed("text-x-script"))),
+                window: None,
+            },
+            action: match result.action {
+                Some(action) => action.into(),
+                None => Action::Callback {
+                    script: script.to_path_buf(),
+                    id: result.id,
+                },
+            },
+            options: Vec::new(),
+            details: result.details,
+        })
+        .collect()
+}
+
+/// Calls `script` back with the `id` of an activated result, and returns
+/// the action it answered with, if any.
+pub async fn activate(script: PathBuf, id: String) -> Result<Option<Action>, String> {
+    let output = call(&script, "activate", &id, ACTIVATE_TIMEOUT)
+        .await
+        .map_err(|err| format!("{}: {err}", script.display()))?;
+    match output.lines().find(|line| !line.trim().is_empty()) {
+        Some(line) => serde_json::from_str::<ScriptAction>(line)
+            .map(|action| Some(action.into()))
+            .map_err(|err| format!("{}: invalid action: {err}", script.display())),
+        None => Ok(None),
+    }
+}
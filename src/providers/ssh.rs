@@ -0,0 +1,397 @@
+//! SSH hosts, listed when the query starts with `ssh`.
+//!
+//! Hosts are read from `~/.ssh/config`, following its `Include`s and leaving
+//! out the `Host` patterns with wildcards, and from the known hosts. Hashed
+//! known hosts cannot be listed, but a host typed in full is offered when
+//! it is one of them.
+//!
+//! The files are read again when one of them, or a directory of their
+//! `Include`s, was modified since.
+
+use std::borrow::Cow;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use futures::stream::{self, BoxStream, StreamExt};
+use hmac::{Hmac, Mac};
+use pop_launcher::{IconSource, SearchResult};
+use sha1::Sha1;
+
+use super::{ready, Action, Provider, ProviderResult};
+use crate::{fl, fuzzy};
+
+const KEYWORD: &str = "ssh";
+const MAX_RESULTS: usize = 10;
+/// Nested `Include`s followed, as a guard against include loops.
+const MAX_INCLUDE_DEPTH: usize = 16;
+const DEFAULT_PORT: u16 = 22;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Host {
+    /// The name passed to `ssh`, an alias of the config or a host name.
+    name: String,
+    hostname: Option<String>,
+    user: Option<String>,
+    port: Option<u16>,
+}
+
+impl Host {
+    fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// Such as `admin@example.com:2222`, leaving out what is not set.
+    fn description(&self) -> String {
+        let mut description = String::new();
+        if let Some(user) = self.user.as_ref() {
+            description.push_str(user);
+            description.push('@');
+        }
+        description.push_str(self.hostname.as_deref().unwrap_or(&self.name));
+        if let Some(port) = self.port {
+            description.push_str(&format!(":{port}"));
+        }
+        description
+    }
+
+    /// The `ssh` command line connecting to the host.
+    fn command(&self) -> Vec<String> {
+        let mut command = vec![KEYWORD.to_string()];
+        // the config has the user and port of its aliases
+        if self.hostname.is_some() {
+            command.push(self.name.clone());
+            return command;
+        }
+        if let Some(port) = self.port {
+            command.extend(["-p".to_string(), port.to_string()]);
+        }
+        command.push(match self.user.as_ref() {
+            Some(user) => format!("{user}@{}", self.name),
+            None => self.name.clone(),
+        });
+        command
+    }
+}
+
+/// A hashed entry of the known hosts: the salt and the HMAC-SHA1 of the
+/// host name.
+struct HashedHost {
+    salt: Vec<u8>,
+    hash: Vec<u8>,
+}
+
+impl HashedHost {
+    fn parse(entry: &str) -> Option<Self> {
+        let (salt, hash) = entry.strip_prefix("|1|")?.split_once('|')?;
+        Some(Self {
+            salt: base64::decode(salt).ok()?,
+            hash: base64::decode(hash).ok()?,
+        })
+    }
+
+    /// Whether the entry is for `host` on `port`, hashed as `host` on the
+    /// default port and as `[host]:port` on others.
+    fn matches(&self, host: &str, port: u16) -> bool {
+        let name = match port {
+            DEFAULT_PORT => host.to_string(),
+            port => format!("[{host}]:{port}"),
+        };
+        let mut mac = match Hmac::<Sha1>::new_from_slice(&self.salt) {
+            Ok(mac) => mac,
+            Err(_) => return false,
+        };
+        mac.update(name.as_bytes());
+        mac.verify_slice(&self.hash).is_ok()
+    }
+}
+
+#[derive(Default)]
+pub struct SshHosts {
+    /// Read and searched on a blocking thread, one search at a time.
+    hosts: Arc<Mutex<Hosts>>,
+}
+
+impl Provider for SshHosts {
+    fn name(&self) -> &'static str {
+        "ssh"
+    }
+
+    fn search_prefixed(&mut self, query: &str) -> BoxStream<'static, Vec<ProviderResult>> {
+        self.search(&format!("{KEYWORD} {query}"))
+    }
+
+    fn search(&mut self, query: &str) -> BoxStream<'static, Vec<ProviderResult>> {
+        let pattern = match query.trim_start().strip_prefix(KEYWORD) {
+            Some(rest) if rest.is_empty() || rest.starts_with(char::is_whitespace) => rest.trim(),
+            _ => return ready(Vec::new()),
+        };
+        let ssh_dir = match std::env::var_os("HOME") {
+            Some(home) => PathBuf::from(home).join(".ssh"),
+            None => return ready(Vec::new()),
+        };
+        let hosts = self.hosts.clone();
+        let pattern = pattern.to_string();
+        let search = move || {
+            let mut hosts = hosts.lock().unwrap();
+            hosts.refresh(&ssh_dir);
+            hosts.search(&pattern)
+        };
+        stream::once(async move {
+            tokio::task::spawn_blocking(search)
+                .await
+                .unwrap_or_default()
+        })
+        .boxed()
+    }
+}
+
+/// The hosts of the config and of the known hosts, read again once one of
+/// the files changed.
+#[derive(Default)]
+struct Hosts {
+    hosts: Vec<Host>,
+    hashed: Vec<HashedHost>,
+    /// The files read, and the directories of the included patterns, along
+    /// with their modification times when they were read.
+    sources: Vec<(PathBuf, Option<SystemTime>)>,
+}
+
+impl Hosts {
+    fn refresh(&mut self, ssh_dir: &Path) {
+        let unchanged = |(path, time): &(PathBuf, Option<SystemTime>)| modified(path) == *time;
+        if !self.sources.is_empty() && self.sources.iter().all(unchanged) {
+            return;
+        }
+        let mut hosts = Vec::new();
+        let mut hashed = Vec::new();
+        let mut sources = Vec::new();
+        read_config(
+            &ssh_dir.join("config"),
+            ssh_dir,
+            &mut hosts,
+            &mut sources,
+            0,
+        );
+        for path in [
+            ssh_dir.join("known_hosts"),
+            PathBuf::from("/etc/ssh/ssh_known_hosts"),
+        ] {
+            read_known_hosts(&path, &mut hosts, &mut hashed, &mut sources);
+        }
+        *self = Self {
+            hosts,
+            hashed,
+            sources,
+        };
+    }
+
+    fn search(&self, pattern: &str) -> Vec<ProviderResult> {
+        let mut matches: Vec<Host> = self
+            .hosts
+            .iter()
+            .filter(|host| fuzzy::is_match(pattern, &host.name))
+            .take(MAX_RESULTS)
+            .cloned()
+            .collect();
+        if !matches.iter().any(|host| host.name == pattern) {
+            if let Some(host) = typed_host(pattern).filter(|host| {
+                let port = host.port.unwrap_or(DEFAULT_PORT);
+                self.hashed
+                    .iter()
+                    .any(|entry| entry.matches(&host.name, port))
+            }) {
+                matches.insert(0, host);
+            }
+        }
+
+        matches
+            .into_iter()
+            .map(|host| ProviderResult {
+                result: SearchResult {
+                    id: 0,
+                    name: host.name.clone(),
+                    description: host.description(),
+                    icon: Some(IconSource::Name(Cow::Borrowed("network-server"))),
+                    category_icon: Some(IconSource::Name(Cow::Borrowed("utilities-terminal"))),
+                    window: None,
+                },
+                action: Action::Terminal(host.command()),
+                options: Vec::new(),
+                details: vec![(fl!("preview-command"), host.command().join(" "))],
+            })
+            .collect()
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// A host typed as `host`, `user@host` or `host:port`.
+fn typed_host(text: &str) -> Option<Host> {
+    if text.is_empty() || text.contains(char::is_whitespace) {
+        return None;
+    }
+    let (user, rest) = match text.split_once('@') {
+        Some((user, rest)) => (Some(user.to_string()), rest),
+        None => (None, text),
+    };
+    let (name, port) = match rest.rsplit_once(':') {
+        Some((name, port)) => (name, Some(port.parse().ok()?)),
+        None => (rest, None),
+    };
+    Some(Host {
+        user,
+        port,
+        ..Host::new(name)
+    })
+}
+
+/// Adds the hosts of the SSH config at `path`, and of the files it
+/// includes, to `hosts`, and what was read to `sources`.
+fn read_config(
+    path: &Path,
+    ssh_dir: &Path,
+    hosts: &mut Vec<Host>,
+    sources: &mut Vec<(PathBuf, Option<SystemTime>)>,
+    depth: usize,
+) {
+    sources.push((path.to_path_buf(), modified(path)));
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(_) => return,
+    };
+    // the hosts of the `Host` line the following options apply to
+    let mut block = 0..0;
+    for line in contents.lines() {
+        let (keyword, value) = match option(line) {
+            Some(option) => option,
+            None => continue,
+        };
+        match keyword.to_ascii_lowercase().as_str() {
+            "include" if depth < MAX_INCLUDE_DEPTH => {
+                for pattern in value.split_whitespace() {
+                    let (dir, includes) = expand_include(pattern, ssh_dir);
+                    // files added to the directory are included as well
+                    let time = modified(&dir);
+                    sources.push((dir, time));
+                    for include in includes {
+                        read_config(&include, ssh_dir, hosts, sources, depth + 1);
+                    }
+                }
+            }
+            "host" => {
+                let new: Vec<Host> = value
+                    .split_whitespace()
+                    .filter(|pattern| !pattern.contains(['*', '?', '!']))
+                    .filter(|pattern| !hosts.iter().any(|host| &host.name == pattern))
+                    .map(Host::new)
+                    .collect();
+                let start = hosts.len();
+                hosts.extend(new);
+                block = start..hosts.len();
+            }
+            "match" => block = 0..0,
+            // the first value of an option is the one used
+            "hostname" => {
+                for host in &mut hosts[block.clone()] {
+                    host.hostname.get_or_insert_with(|| value.to_string());
+                }
+            }
+            "user" => {
+                for host in &mut hosts[block.clone()] {
+                    host.user.get_or_insert_with(|| value.to_string());
+                }
+            }
+            "port" => {
+                if let Ok(port) = value.parse() {
+                    for host in &mut hosts[block.clone()] {
+                        host.port.get_or_insert(port);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The keyword and value of a config line, separated by whitespace or `=`.
+fn option(line: &str) -> Option<(&str, &str)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let end = line.find(|c: char| c.is_whitespace() || c == '=')?;
+    let (keyword, value) = line.split_at(end);
+    let value = value.trim_start().strip_prefix('=').unwrap_or(value).trim();
+    Some((keyword, value.trim_matches('"')))
+}
+
+/// The directory of an `Include` pattern, relative to `~/.ssh` unless it
+/// is absolute, and the files matching it.
+fn expand_include(pattern: &str, ssh_dir: &Path) -> (PathBuf, Vec<PathBuf>) {
+    let pattern = match pattern.strip_prefix("~/") {
+        Some(rest) => ssh_dir.parent().unwrap_or(ssh_dir).join(rest),
+        None => ssh_dir.join(pattern),
+    };
+    let dir = pattern.parent().unwrap_or(ssh_dir).to_path_buf();
+    let includes = match glob::glob(&pattern.to_string_lossy()) {
+        Ok(paths) => paths.flatten().collect(),
+        Err(_) => Vec::new(),
+    };
+    (dir, includes)
+}
+
+/// Adds the hosts of the known hosts file at `path` that are not listed
+/// yet to `hosts`, its hashed entries to `hashed`, and the file to
+/// `sources`.
+fn read_known_hosts(
+    path: &Path,
+    hosts: &mut Vec<Host>,
+    hashed: &mut Vec<HashedHost>,
+    sources: &mut Vec<(PathBuf, Option<SystemTime>)>,
+) {
+    sources.push((path.to_path_buf(), modified(path)));
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(_) => return,
+    };
+    for line in contents.lines().map(str::trim) {
+        // revoked keys and certificate authorities are not hosts
+        if line.is_empty() || line.starts_with('#') || line.starts_with('@') {
+            continue;
+        }
+        let names = line.split_whitespace().next().unwrap_or_default();
+        if names.starts_with('|') {
+            hashed.extend(HashedHost::parse(names));
+            continue;
+        }
+        for name in names.split(',') {
+            if name.contains(['*', '?', '!']) {
+                continue;
+            }
+            let host = match name
+                .strip_prefix('[')
+                .and_then(|name| name.split_once("]:"))
+            {
+                Some((name, port)) => Host {
+                    port: port.parse().ok(),
+                    ..Host::new(name)
+                },
+                None => Host::new(name),
+            };
+            let listed = hosts
+                .iter()
+                .any(|h| h.name == host.name || h.hostname.as_deref() == Some(&host.name));
+            // addresses are listed along with the names of the same hosts
+            if !listed && host.name.parse::<std::net::IpAddr>().is_err() {
+                hosts.push(host);
+            }
+        }
+    }
+}
@@ -0,0 +1,155 @@
+//! Web addresses and searches typed into the launcher.
+//!
+//! `g rust tokio`, or the bang `!g rust tokio`, searches with the engine
+//! whose keyword is `g`, and queries typed after a prefix of the provider
+//! search with the first engine. Addresses such as `example.com/docs` are
+//! opened as typed, with `https://` added when the scheme is left out.
+
+use std::borrow::Cow;
+
+use futures::stream::BoxStream;
+use pop_launcher::{IconSource, SearchResult};
+
+use super::{ready, Action, Provider, ProviderResult};
+use crate::preferences::SearchEngine;
+use crate::{fl, uri};
+
+const ICON: &str = "web-browser";
+/// Top-level domains of addresses typed without a scheme or path, so that
+/// file names such as `main.rs` are not taken for one.
+const COMMON_TLDS: &[&str] = &[
+    "ai", "app", "au", "br", "ca", "ch", "co", "com", "de", "dev", "edu", "es", "eu", "fr", "gov",
+    "info", "io", "it", "jp", "me", "net", "nl", "no", "org", "pl", "ru", "se", "uk", "us", "xyz",
+];
+
+pub struct Web {
+    engines: Vec<SearchEngine>,
+}
+
+impl Web {
+    pub fn new(engines: Vec<SearchEngine>) -> Self {
+        Self { engines }
+    }
+
+    /// The engine of the keyword starting `query`, and the search terms.
+    fn engine<'a>(&self, query: &'a str) -> Option<(&SearchEngine, &'a str)> {
+        let (keyword, terms) = query.split_once(char::is_whitespace)?;
+        let keyword = keyword.strip_prefix('!').unwrap_or(keyword);
+        let terms = terms.trim();
+        if terms.is_empty() {
+            return None;
+        }
+        self.engines
+            .iter()
+            .find(|engine| engine.keyword == keyword)
+            .map(|engine| (engine, terms))
+    }
+}
+
+impl Provider for Web {
+    fn name(&self) -> &'static str {
+        "web"
+    }
+
+    fn search(&mut self, query: &str) -> BoxStream<'static, Vec<ProviderResult>> {
+        let query = query.trim();
+        if let Some(url) = normalize_url(query) {
+            return ready(vec![result(url.clone(), fl!("web-open"), url)]);
+        }
+        match self.engine(query) {
+            Some((engine, terms)) => ready(vec![search_result(engine, terms)]),
+            None => ready(Vec::new()),
+        }
+    }
+
+    fn search_prefixed(&mut self, query: &str) -> BoxStream<'static, Vec<ProviderResult>> {
+        let query = query.trim();
+        if query.is_empty() || normalize_url(query).is_some() || self.engine(query).is_some() {
+            return self.search(query);
+        }
+        match self.engines.first() {
+            Some(engine) => ready(vec![search_result(engine, query)]),
+            None => ready(Vec::new()),
+        }
+    }
+}
+
+/// The result searching `terms` with `engine`.
+fn search_result(engine: &SearchEngine, terms: &str) -> ProviderResult {
+    let url = engine.url.replace("%s", &uri::encode_component(terms));
+    let description = fl!("web-search", engine = engine.name.clone());
+    result(terms.to_string(), description, url)
+}
+
+fn result(name: String, description: String, url: String) -> ProviderResult {
+    ProviderResult {
+        result: SearchResult {
+            id: 0,
+            name,
+            description,
+            icon: Some(IconSource::Name(Cow::Borrowed(ICON))),
+            category_icon: Some(IconSource::Name(Cow::Borrowed(ICON))),
+            window: None,
+        },
+        details: vec![(fl!("preview-address"), url.clone())],
+        action: Action::Open {
+            uri: url,
+            app: None,
+        },
+        options: Vec::new(),
+    }
+}
+
+/// The address `text` stands for, if it looks like one: a `http` or
+/// `https` URL, `localhost`, an IPv4 address or a domain name, each
+/// optionally followed by a port and a path.
+fn normalize_url(text: &str) -> Option<String> {
+    if text.is_empty() || text.contains(char::is_whitespace) {
+        return None;
+    }
+    let lower = text.to_ascii_lowercase();
+    if let Some(rest) = ["http://", "https://"]
+        .iter()
+        .find_map(|scheme| lower.strip_prefix(scheme))
+    {
+        return (!rest.is_empty()).then(|| text.to_string());
+    }
+
+    let end = text.find(['/', '?', '#']).unwrap_or(text.len());
+    let (authority, path) = text.split_at(end);
+    let (host, port) = match authority.rsplit_once(':') {
+        Some((host, port)) => (host, Some(port)),
+        None => (authority, None),
+    };
+    let invalid = |port: &str| port.is_empty() || !port.bytes().all(|b| b.is_ascii_digit());
+    if port.map_or(false, invalid) {
+        return None;
+    }
+    let explicit = port.is_some() || !path.is_empty();
+
+    if host.eq_ignore_ascii_case("localhost") {
+        return Some(format!("http://{text}"));
+    }
+    let labels: Vec<&str> = host.split('.').collect();
+    if labels.len() == 4 && labels.iter().all(|l| l.parse::<u8>().is_ok()) {
+        return explicit.then(|| format!("http://{text}"));
+    }
+    let valid = labels.len() >= 2
+        && labels.iter().all(|label| {
+            !label.is_empty()
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b == b'-')
+        });
+    let tld = labels
+        .last()
+        .copied()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    if !valid || tld.len() < 2 || !tld.bytes().all(|b| b.is_ascii_alphabetic()) {
+        return None;
+    }
+    (explicit || COMMON_TLDS.contains(&tld.as_str())).then(|| format!("https://{text}"))
+}
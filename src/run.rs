@@ -0,0 +1,134 @@
+//! Completions for the run mode, from the executables on `$PATH` and the
+//! history of the shells.
+
+use std::collections::HashSet;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+
+/// Completions offered at once, the executables starting with a single
+/// letter being too many to list.
+const MAX_MATCHES: usize = 50;
+/// Shell history entries read, from the latest.
+const MAX_HISTORY: usize = 1000;
+
+#[derive(Debug, Clone, Default)]
+pub struct Completions {
+    /// Names of the executables on `$PATH`, sorted.
+    executables: Vec<String>,
+    /// Commands from the shell histories, latest first.
+    history: Vec<String>,
+}
+
+impl Completions {
+    pub fn load() -> Self {
+        Self {
+            executables: executables(),
+            history: history(),
+        }
+    }
+
+    /// History entries starting with `input`, then executables starting
+    /// with it while only the command name has been typed. The latest
+    /// history entries are listed while nothing has been typed.
+    pub fn matches(&self, input: &str) -> Vec<&str> {
+        let input = input.trim_start();
+        let history = self
+            .history
+            .iter()
+            .map(String::as_str)
+            .filter(|command| command.starts_with(input) && *command != input);
+        let executables = self.executables.iter().map(String::as_str).filter(|name| {
+            !input.is_empty()
+                && !input.contains(char::is_whitespace)
+                && name.starts_with(input)
+                && *name != input
+        });
+
+        let mut seen = HashSet::new();
+        history
+            .chain(executables)
+            .filter(|command| seen.insert(*command))
+            .take(MAX_MATCHES)
+            .collect()
+    }
+}
+
+fn executables() -> Vec<String> {
+    let path = std::env::var_os("PATH").unwrap_or_default();
+    let mut names: Vec<String> = std::env::split_paths(&path)
+        .filter_map(|dir| fs::read_dir(dir).ok())
+        .flatten()
+        .flatten()
+        .filter(|entry| {
+            // follows links, which most of `/usr/bin` is made of
+            fs::metadata(entry.path())
+                .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+                .unwrap_or_default()
+        })
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect();
+    names.sort_unstable();
+    names.dedup();
+    names
+}
+
+/// Commands of the bash, zsh and fish histories, latest first and without
+/// duplicates.
+fn history() -> Vec<String> {
+    let home = match std::env::var_os("HOME") {
+        Some(home) => PathBuf::from(home),
+        None => return Vec::new(),
+    };
+    let zdotdir = std::env::var_os("ZDOTDIR").map_or_else(|| home.clone(), PathBuf::from);
+    let data = xdg::BaseDirectories::new()
+        .map(|dirs| dirs.get_data_home())
+        .unwrap_or_else(|_| home.join(".local/share"));
+
+    let mut commands = Vec::new();
+    commands.extend(read_history(home.join(".bash_history"), bash_command));
+    commands.extend(read_history(zdotdir.join(".zsh_history"), zsh_command));
+    commands.extend(read_history(data.join("fish/fish_history"), fish_command));
+
+    let mut seen = HashSet::new();
+    commands
+        .into_iter()
+        .rev()
+        .filter(|command| seen.insert(command.clone()))
+        .take(MAX_HISTORY)
+        .collect()
+}
+
+/// The commands of the history file at `path`, oldest first.
+fn read_history(path: PathBuf, command: fn(&str) -> Option<&str>) -> Vec<String> {
+    // zsh writes some characters escaped in its own way, which are lost
+    let bytes = fs::read(path).unwrap_or_default();
+    String::from_utf8_lossy(&bytes)
+        .lines()
+        .filter_map(command)
+        .map(str::trim)
+        .filter(|command| !command.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Skips the `#1690000000` timestamps written with `HISTTIMEFORMAT`.
+fn bash_command(line: &str) -> Option<&str> {
+    match line.strip_prefix('#') {
+        Some(time) if time.chars().all(|c| c.is_ascii_digit()) => None,
+        _ => Some(line),
+    }
+}
+
+/// Strips the `: 1690000000:0;` of extended history.
+fn zsh_command(line: &str) -> Option<&str> {
+    match line.strip_prefix(": ") {
+        Some(extended) => extended.split_once(';').map(|(_, command)| command),
+        None => Some(line),
+    }
+}
+
+/// Picks the `- cmd: ` lines of the YAML-like fish history.
+fn fish_command(line: &str) -> Option<&str> {
+    line.strip_prefix("- cmd: ")
+}
@@ -0,0 +1,207 @@
+//! Starting applications and commands outside of the launcher.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+
+use freedesktop_desktop_entry::DesktopEntry;
+use tokio::process::Command;
+
+use crate::uri;
+
+/// How long a command is watched for failing right away.
+const FAILURE_TIMEOUT: Duration = Duration::from_millis(300);
+/// Terminals tried in order when `$TERMINAL` is not set.
+const TERMINALS: &[&str] = &[
+    "x-terminal-emulator",
+    "cosmic-term",
+    "gnome-terminal",
+    "konsole",
+    "alacritty",
+    "kitty",
+    "foot",
+    "xterm",
+];
+
+/// The command of the desktop entry at `path`, or of its `action`.
+pub fn desktop_entry(path: &Path, action: Option<&str>) -> Option<Command> {
+    entry_command(path, action, &[])
+}
+
+/// The command opening `uris` with the desktop entry at `path`.
+pub fn open_with(path: &Path, uris: &[String]) -> Option<Command> {
+    entry_command(path, None, uris)
+}
+
+/// Opens `uri` with the default application for its type.
+pub fn open(uri: &str) -> Command {
+    let mut cmd = Command::new("xdg-open");
+    cmd.arg(uri);
+    cmd
+}
+
+/// Opens `uri` through the OpenURI portal, which also works from a sandbox,
+/// and with `xdg-open` if the portal is not available.
+///
+/// Local files always go to `xdg-open`, as the portal only takes them as
+/// file descriptors.
+pub async fn open_uri(uri: String) -> Result<(), String> {
+    if !uri.starts_with("file:") {
+        match portal_open_uri(&uri).await {
+            Ok(()) => return Ok(()),
+            Err(err) => log::debug!("failed to open {uri} through the portal: {err}"),
+        }
+    }
+    run(open(&uri)).await
+}
+
+async fn portal_open_uri(uri: &str) -> zbus::Result<()> {
+    let conn = zbus::Connection::session().await?;
+    let options: HashMap<&str, zbus::zvariant::Value> = HashMap::new();
+    conn.call_method(
+        Some("org.freedesktop.portal.Desktop"),
+        "/org/freedesktop/portal/desktop",
+        Some("org.freedesktop.portal.OpenURI"),
+        "OpenURI",
+        // no parent window
+        &("", uri, options),
+    )
+    .await?;
+    Ok(())
+}
+
+/// The command of an entry or one of its actions, run in the working
+/// directory of the `Path` key.
+fn entry_command(path: &Path, action: Option<&str>, uris: &[String]) -> Option<Command> {
+    let bytes = fs::read_to_string(path).ok()?;
+    let entry = DesktopEntry::decode(path, &bytes).ok()?;
+    let mut cmd = match action {
+        Some(action) => exec(entry.action_exec(action)?, uris)?,
+        None => exec(entry.exec()?, uris)?,
+    };
+    if let Some(dir) = entry.desktop_entry("Path").filter(|dir| !dir.is_empty()) {
+        cmd.current_dir(dir);
+    }
+    Some(cmd)
+}
+
+/// The command of an `Exec` value, its file and URL field codes replaced by
+/// `uris` and the other field codes left out.
+///
+/// The URIs are appended if the value has no field code for them, and only
+/// the first is passed to the codes taking a single one.
+fn exec(exec: &str, uris: &[String]) -> Option<Command> {
+    let mut args = Vec::new();
+    let mut expanded = false;
+    for arg in shlex::Shlex::new(exec) {
+        match arg.as_str() {
+            "%f" | "%F" | "%u" | "%U" => expanded = true,
+            _ => {}
+        }
+        match arg.as_str() {
+            "%f" => args.extend(uris.iter().find_map(|uri| path_arg(uri))),
+            "%F" => args.extend(uris.iter().filter_map(|uri| path_arg(uri))),
+            "%u" => args.extend(uris.first().cloned()),
+            "%U" => args.extend(uris.iter().cloned()),
+            arg if arg.len() == 2 && arg.starts_with('%') && arg != "%%" => {}
+            arg => args.push(arg.replace("%%", "%")),
+        }
+    }
+    if !expanded {
+        args.extend(uris.iter().cloned());
+    }
+
+    let mut args = args.into_iter();
+    let mut cmd = Command::new(args.next()?);
+    cmd.args(args);
+    Some(cmd)
+}
+
+/// The path of a local file URI, as passed to `%f` and `%F`.
+fn path_arg(uri: &str) -> Option<String> {
+    uri::to_path(uri).map(|path| path.to_string_lossy().into_owned())
+}
+
+/// Runs `command` with the shell.
+pub fn shell(command: &str) -> Command {
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c").arg(command);
+    cmd
+}
+
+/// Runs `command` in a terminal, which stays open with an interactive shell
+/// once it exits.
+pub fn in_terminal(command: &str) -> Option<Command> {
+    let (terminal, separator) = terminal()?;
+    let mut cmd = Command::new(terminal);
+    cmd.args([
+        separator,
+        "sh",
+        "-c",
+        &format!("{command}; exec \"${{SHELL:-sh}}\""),
+    ]);
+    Some(cmd)
+}
+
+/// Runs the program and arguments of `args` in a terminal, which closes
+/// once the program exits.
+pub fn exec_in_terminal(args: &[String]) -> Option<Command> {
+    let (terminal, separator) = terminal()?;
+    let mut cmd = Command::new(terminal);
+    cmd.arg(separator).args(args);
+    Some(cmd)
+}
+
+/// `$TERMINAL`, or the first of the known terminals that is installed,
+/// along with the option it takes the command after.
+fn terminal() -> Option<(String, &'static str)> {
+    let terminal = std::env::var("TERMINAL")
+        .ok()
+        .filter(|t| !t.is_empty())
+        .or_else(|| TERMINALS.iter().find(|t| which(t)).map(|t| t.to_string()))?;
+    // the GNOME terminals take the command after `--` instead of `-e`
+    let separator = match terminal.as_str() {
+        "gnome-terminal" | "kgx" => "--",
+        _ => "-e",
+    };
+    Some((terminal, separator))
+}
+
+/// Whether `name` is an executable on `$PATH`.
+fn which(name: &str) -> bool {
+    std::env::var_os("PATH")
+        .map(|path| std::env::split_paths(&path).any(|dir| dir.join(name).is_file()))
+        .unwrap_or_default()
+}
+
+/// Starts `cmd` detached from the launcher.
+///
+/// The command is given a moment to fail, so that a missing program or an
+/// immediate non-zero exit status can be reported before the launcher
+/// hides. Commands still running by then are left to run on their own.
+pub async fn run(mut cmd: Command) -> Result<(), String> {
+    // in a session of its own, the command is not sent the signals meant for
+    // the launcher, such as those ending its process group
+    unsafe {
+        cmd.pre_exec(|| {
+            rustix::process::setsid()?;
+            Ok(())
+        });
+    }
+    let mut child = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|err| err.to_string())?;
+    match tokio::time::timeout(FAILURE_TIMEOUT, child.wait()).await {
+        Ok(Ok(status)) if !status.success() => Err(match status.code() {
+            Some(127) => "command not found".to_string(),
+            _ => format!("exited with {status}"),
+        }),
+        Ok(Err(err)) => Err(err.to_string()),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,167 @@
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::preferences::PREFIX;
+
+const FILE: &str = "clipboard-history.json";
+/// Directory that holds copied images, below the data directory if the
+/// history is persisted and below the runtime directory otherwise.
+const IMAGES: &str = "clipboard";
+
+/// Something that was copied.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Clip {
+    Text(String),
+    /// An image, kept in a file named after a hash of its data.
+    Image {
+        mime: String,
+        path: PathBuf,
+    },
+}
+
+/// Recent clipboard contents, oldest first and without duplicates.
+///
+/// The history is only written to disk if it is persisted, and copied
+/// images otherwise live in the runtime directory until the session ends.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ClipboardHistory {
+    clips: Vec<Clip>,
+    #[serde(skip)]
+    capacity: usize,
+    #[serde(skip)]
+    persist: bool,
+}
+
+impl ClipboardHistory {
+    /// Loads the stored history if `persist` is set, and removes it otherwise.
+    pub fn load(capacity: usize, persist: bool) -> Self {
+        let mut history = if persist {
+            super::load(FILE)
+        } else {
+            Self::clear_stored();
+            Self::default()
+        };
+        history.capacity = capacity;
+        history.persist = persist;
+        history.clips.retain(|clip| match clip {
+            Clip::Text(_) => true,
+            Clip::Image { path, .. } => path.exists(),
+        });
+        history.truncate();
+        history
+    }
+
+    /// The clip copied `age` copies ago, `0` being the latest.
+    pub fn get(&self, age: usize) -> Option<&Clip> {
+        self.clips
+            .len()
+            .checked_sub(age + 1)
+            .and_then(|i| self.clips.get(i))
+    }
+
+    /// Clips from the latest to the oldest, along with their age.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &Clip)> {
+        self.clips.iter().rev().enumerate()
+    }
+
+    pub fn push_text(&mut self, text: String) {
+        if !text.trim().is_empty() {
+            self.push(Clip::Text(text));
+        }
+    }
+
+    /// Remembers an image, writing `data` to a file.
+    pub fn push_image(&mut self, mime: String, data: &[u8]) {
+        let mut hasher = DefaultHasher::new();
+        data.hash(&mut hasher);
+        let extension = mime.rsplit('/').next().unwrap_or("bin");
+        let file = format!("{}/{:016x}.{}", IMAGES, hasher.finish(), extension);
+
+        let dirs = match xdg::BaseDirectories::with_prefix(PREFIX) {
+            Ok(dirs) => dirs,
+            Err(err) => {
+                log::error!("no directory to store copied images: {}", err);
+                return;
+            }
+        };
+        let path = if self.persist {
+            dirs.place_data_file(&file)
+        } else {
+            dirs.place_runtime_file(&file)
+        };
+        let path = match path {
+            Ok(path) => path,
+            Err(err) => {
+                log::error!("no directory to store copied images: {}", err);
+                return;
+            }
+        };
+
+        if !path.exists() {
+            if let Err(err) = fs::write(&path, data) {
+                log::error!("failed to write {}: {}", path.display(), err);
+                return;
+            }
+        }
+        self.push(Clip::Image { mime, path });
+    }
+
+    /// Adds `clip` as the latest clip, moving it to the front if it was
+    /// copied before.
+    fn push(&mut self, clip: Clip) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.clips.retain(|c| *c != clip);
+        self.clips.push(clip);
+        self.truncate();
+        self.save();
+    }
+
+    /// Drops the oldest clips beyond the capacity.
+    fn truncate(&mut self) {
+        let excess = self.clips.len().saturating_sub(self.capacity);
+        for clip in self.clips.drain(..excess) {
+            remove_image(&clip);
+        }
+    }
+
+    fn save(&self) {
+        if self.persist {
+            super::save(FILE, self);
+        }
+    }
+
+    /// Forgets the clip copied `age` copies ago.
+    pub fn remove(&mut self, age: usize) {
+        if let Some(i) = self.clips.len().checked_sub(age + 1) {
+            remove_image(&self.clips.remove(i));
+            self.save();
+        }
+    }
+
+    /// Removes the stored history and its images without loading it.
+    pub fn clear_stored() {
+        super::remove(FILE);
+        if let Ok(dirs) = xdg::BaseDirectories::with_prefix(PREFIX) {
+            let images = dirs.get_data_home().join(IMAGES);
+            if images.exists() {
+                if let Err(err) = fs::remove_dir_all(&images) {
+                    log::error!("failed to remove {}: {}", images.display(), err);
+                }
+            }
+        }
+    }
+}
+
+fn remove_image(clip: &Clip) {
+    if let Clip::Image { path, .. } = clip {
+        if let Err(err) = fs::remove_file(path) {
+            log::error!("failed to remove {}: {}", path.display(), err);
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use pop_launcher::SearchResult;
+use serde::{Deserialize, Serialize};
+
+use super::plugin;
+
+const FILE: &str = "favorites.json";
+
+/// A pinned result.
+///
+/// Result ids are only valid for the search that produced them, so the query
+/// is kept as well and searched again when the favorite is activated.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Favorite {
+    pub result: SearchResult,
+    pub query: String,
+}
+
+impl Favorite {
+    /// Whether `result` is the same entry as this favorite.
+    pub fn matches(&self, result: &SearchResult) -> bool {
+        self.result.name == result.name && plugin(&self.result) == plugin(result)
+    }
+}
+
+/// Results pinned by the user, shown in order when the query is empty.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Favorites {
+    entries: Vec<Favorite>,
+}
+
+impl Favorites {
+    pub fn load() -> Self {
+        super::load(FILE)
+    }
+
+    pub fn entries(&self) -> &[Favorite] {
+        &self.entries
+    }
+
+    pub fn get(&self, i: usize) -> Option<&Favorite> {
+        self.entries.get(i)
+    }
+
+    pub fn position(&self, result: &SearchResult) -> Option<usize> {
+        self.entries.iter().position(|f| f.matches(result))
+    }
+
+    /// Pins `result`, or unpins it if it already is a favorite.
+    pub fn toggle(&mut self, result: &SearchResult, query: &str) {
+        match self.position(result) {
+            Some(i) => {
+                self.entries.remove(i);
+            }
+            None => self.entries.push(Favorite {
+                result: result.clone(),
+                query: query.to_string(),
+            }),
+        }
+        super::save(FILE, self);
+    }
+
+    pub fn remove(&mut self, i: usize) {
+        if i < self.entries.len() {
+            self.entries.remove(i);
+            super::save(FILE, self);
+        }
+    }
+
+    /// Moves the favorite at `i` by `offset` places and returns its new position.
+    pub fn move_by(&mut self, i: usize, offset: isize) -> usize {
+        if i >= self.entries.len() {
+            return i;
+        }
+        let target = (i as isize + offset).clamp(0, self.entries.len() as isize - 1) as usize;
+        if target != i {
+            let favorite = self.entries.remove(i);
+            self.entries.insert(target, favorite);
+            super::save(FILE, self);
+        }
+        target
+    }
+}
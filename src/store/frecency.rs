@@ -0,0 +1,222 @@
+use std::cmp::Reverse;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use pop_launcher::SearchResult;
+use serde::{Deserialize, Serialize};
+
+use super::plugin;
+
+const FILE: &str = "frecency.json";
+/// Oldest activations are dropped once the history grows past this.
+const MAX_ACTIVATIONS: usize = 1000;
+/// Number of characters of the query that are remembered with an activation.
+const QUERY_PREFIX_LEN: usize = 8;
+
+const DAY: u64 = 24 * 60 * 60;
+
+/// A single launch of a result.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Activation {
+    name: String,
+    plugin: String,
+    query: String,
+    timestamp: u64,
+}
+
+/// History of activated results, used to rank results that are launched
+/// often and recently above the order returned by the launcher service.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Frecency {
+    activations: Vec<Activation>,
+}
+
+impl Frecency {
+    pub fn load() -> Self {
+        super::load(FILE)
+    }
+
+    /// Records the activation of `result` for `query` and persists the history
+    /// in the background.
+    pub fn record(&mut self, result: &SearchResult, query: &str) {
+        if self.activations.len() >= MAX_ACTIVATIONS {
+            self.activations
+                .drain(..=self.activations.len() - MAX_ACTIVATIONS);
+        }
+        self.activations.push(Activation {
+            name: result.name.clone(),
+            plugin: plugin(result),
+            query: query_prefix(query),
+            timestamp: now(),
+        });
+        super::save_in_background(FILE, self);
+    }
+
+    /// Stable sorts `results` by descending frecency for `query`.
+    pub fn rank<T>(&self, query: &str, results: &mut [T], result: impl Fn(&T) -> &SearchResult) {
+        if self.activations.is_empty() {
+            return;
+        }
+        let query = query_prefix(query);
+        let now = now();
+        results.sort_by_cached_key(|item| Reverse(self.score(result(item), &query, now)));
+    }
+
+    pub fn clear(&mut self) {
+        self.activations.clear();
+        super::remove_in_background(FILE);
+    }
+
+    /// Removes the stored history without loading it.
+    pub fn clear_stored() {
+        super::remove(FILE);
+    }
+
+    /// Sums the activations of `result` for queries that `query` continues or
+    /// that continue it, weighting them by their age.
+    fn score(&self, result: &SearchResult, query: &str, now: u64) -> u64 {
+        let plugin = plugin(result);
+        self.activations
+            .iter()
+            .filter(|a| a.name == result.name && a.plugin == plugin)
+            .filter(|a| a.query.starts_with(query) || query.starts_with(&a.query))
+            .map(|a| match now.saturating_sub(a.timestamp) / DAY {
+                0..=3 => 100,
+                4..=13 => 70,
+                14..=30 => 50,
+                31..=90 => 30,
+                _ => 10,
+            })
+            .sum()
+    }
+}
+
+fn query_prefix(query: &str) -> String {
+    query
+        .trim_start()
+        .to_lowercase()
+        .chars()
+        .take(QUERY_PREFIX_LEN)
+        .collect()
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use pop_launcher::IconSource;
+
+    use super::*;
+
+    fn result(name: &str) -> SearchResult {
+        SearchResult {
+            id: 0,
+            name: name.to_string(),
+            description: String::new(),
+            icon: None,
+            category_icon: Some(IconSource::Name(Cow::Borrowed("desktop"))),
+            window: None,
+        }
+    }
+
+    fn activation(name: &str, query: &str, days_ago: u64) -> Activation {
+        Activation {
+            name: name.to_string(),
+            plugin: "desktop".to_string(),
+            query: query.to_string(),
+            timestamp: now() - days_ago * DAY,
+        }
+    }
+
+    fn ranked(frecency: &Frecency, query: &str, names: &[&str]) -> Vec<String> {
+        let mut results: Vec<SearchResult> = names.iter().map(|name| result(name)).collect();
+        frecency.rank(query, &mut results, |r| r);
+        results.into_iter().map(|r| r.name).collect()
+    }
+
+    #[test]
+    fn score_matches_either_prefix() {
+        let frecency = Frecency {
+            activations: vec![
+                activation("Firefox", "fi", 0),
+                activation("Firefox", "fire", 0),
+            ],
+        };
+        let now = now();
+        let firefox = result("Firefox");
+        assert_eq!(frecency.score(&firefox, "f", now), 200);
+        assert_eq!(frecency.score(&firefox, "fir", now), 200);
+        assert_eq!(frecency.score(&firefox, "firefox", now), 200);
+        assert_eq!(frecency.score(&firefox, "fo", now), 0);
+    }
+
+    #[test]
+    fn score_by_age() {
+        let frecency = Frecency {
+            activations: vec![
+                activation("Files", "f", 1),
+                activation("Files", "f", 10),
+                activation("Files", "f", 20),
+                activation("Files", "f", 60),
+                activation("Files", "f", 365),
+            ],
+        };
+        assert_eq!(
+            frecency.score(&result("Files"), "f", now()),
+            100 + 70 + 50 + 30 + 10
+        );
+    }
+
+    #[test]
+    fn score_only_the_same_result() {
+        let mut other_plugin = activation("Files", "f", 0);
+        other_plugin.plugin = "folder".to_string();
+        let frecency = Frecency {
+            activations: vec![other_plugin, activation("Firefox", "f", 0)],
+        };
+        assert_eq!(frecency.score(&result("Files"), "f", now()), 0);
+    }
+
+    #[test]
+    fn rank_frecent_first() {
+        let frecency = Frecency {
+            activations: vec![
+                activation("Files", "f", 0),
+                activation("Firefox", "f", 0),
+                activation("Firefox", "fi", 40),
+            ],
+        };
+        assert_eq!(
+            ranked(&frecency, "fi", &["Fish", "Files", "Firefox"]),
+            ["Firefox", "Files", "Fish"]
+        );
+        // ties keep the order of the service
+        assert_eq!(
+            ranked(&frecency, "x", &["Fish", "Files", "Firefox"]),
+            ["Fish", "Files", "Firefox"]
+        );
+    }
+
+    #[test]
+    fn rank_by_query_prefix() {
+        let query = query_prefix("  TERMINAL EMULATOR");
+        assert_eq!(query, "terminal");
+        let frecency = Frecency {
+            activations: vec![activation("Terminal", &query, 0)],
+        };
+        assert_eq!(
+            ranked(&frecency, "Term", &["Tetris", "Terminal"]),
+            ["Terminal", "Tetris"]
+        );
+        assert_eq!(
+            ranked(&frecency, "terminal emulator", &["Tetris", "Terminal"]),
+            ["Terminal", "Tetris"]
+        );
+    }
+}
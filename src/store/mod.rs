@@ -0,0 +1,126 @@
+//! Small JSON files persisted under `$XDG_DATA_HOME/iced-launcher/`.
+
+pub mod clipboard_history;
+pub mod favorites;
+pub mod frecency;
+pub mod query_history;
+pub mod recent_emoji;
+
+use std::sync::{mpsc, Mutex};
+use std::{fs, path::PathBuf, thread};
+
+use once_cell::sync::Lazy;
+use pop_launcher::{IconSource, SearchResult};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::preferences::PREFIX;
+
+/// The contents to write to a file, or `None` to remove it.
+type Write = (&'static str, Option<String>);
+
+/// Thread writing the files of `save_in_background` in the order they were
+/// passed.
+static WRITER: Lazy<Mutex<mpsc::Sender<Write>>> = Lazy::new(|| {
+    let (tx, rx) = mpsc::channel::<Write>();
+    thread::spawn(move || {
+        for (file, contents) in rx {
+            match contents {
+                Some(contents) => write(file, &contents),
+                None => remove(file),
+            }
+        }
+    });
+    Mutex::new(tx)
+});
+
+fn path(file: &str) -> Option<PathBuf> {
+    xdg::BaseDirectories::with_prefix(PREFIX)
+        .ok()
+        .and_then(|dirs| dirs.place_data_file(file).ok())
+}
+
+/// Reads `file`, falling back to the default value if it is missing or invalid.
+pub fn load<T: DeserializeOwned + Default>(file: &str) -> T {
+    let path = match path(file) {
+        Some(path) if path.exists() => path,
+        _ => return T::default(),
+    };
+
+    match fs::read_to_string(&path).map(|s| serde_json::from_str(&s)) {
+        Ok(Ok(value)) => value,
+        Ok(Err(err)) => {
+            log::error!("invalid data in {}: {}", path.display(), err);
+            T::default()
+        }
+        Err(err) => {
+            log::error!("failed to read {}: {}", path.display(), err);
+            T::default()
+        }
+    }
+}
+
+pub fn save<T: Serialize>(file: &str, value: &T) {
+    match serde_json::to_string(value) {
+        Ok(contents) => write(file, &contents),
+        Err(err) => log::error!("failed to serialize {}: {}", file, err),
+    }
+}
+
+/// Like `save`, but leaves writing the file to a background thread, for
+/// files too large to write while the launcher is waiting.
+pub fn save_in_background<T: Serialize>(file: &'static str, value: &T) {
+    match serde_json::to_string(value) {
+        Ok(contents) => send(file, Some(contents)),
+        Err(err) => log::error!("failed to serialize {}: {}", file, err),
+    }
+}
+
+/// Like `remove`, but after the writes already left to the background thread.
+pub fn remove_in_background(file: &'static str) {
+    send(file, None);
+}
+
+fn send(file: &'static str, contents: Option<String>) {
+    let res = match WRITER.lock() {
+        Ok(writer) => writer.send((file, contents)),
+        Err(_) => Err(mpsc::SendError((file, contents))),
+    };
+    // the thread is gone, so nothing else is writing the file
+    if let Err(mpsc::SendError((file, contents))) = res {
+        match contents {
+            Some(contents) => write(file, &contents),
+            None => remove(file),
+        }
+    }
+}
+
+fn write(file: &str, contents: &str) {
+    let path = match path(file) {
+        Some(path) => path,
+        None => {
+            log::error!("no data directory to store {}", file);
+            return;
+        }
+    };
+
+    if let Err(err) = fs::write(&path, contents) {
+        log::error!("failed to write {}: {}", path.display(), err);
+    }
+}
+
+pub fn remove(file: &str) {
+    if let Some(path) = path(file).filter(|path| path.exists()) {
+        if let Err(err) = fs::remove_file(&path) {
+            log::error!("failed to remove {}: {}", path.display(), err);
+        }
+    }
+}
+
+/// The plugin a result came from isn't part of `SearchResult`, but plugins
+/// set their own icon as the category icon, which identifies them well enough.
+pub fn plugin(result: &SearchResult) -> String {
+    match result.category_icon.as_ref() {
+        Some(IconSource::Name(name) | IconSource::Mime(name)) => name.to_string(),
+        None => String::new(),
+    }
+}
@@ -0,0 +1,122 @@
+use serde::{Deserialize, Serialize};
+
+const FILE: &str = "query-history.json";
+/// Oldest queries are dropped once the history grows past this.
+const MAX_QUERIES: usize = 200;
+
+/// Submitted queries, oldest first and without duplicates.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QueryHistory {
+    queries: Vec<String>,
+}
+
+impl QueryHistory {
+    pub fn load() -> Self {
+        super::load(FILE)
+    }
+
+    /// The query `age` submissions ago, `0` being the latest.
+    pub fn get(&self, age: usize) -> Option<&str> {
+        self.queries
+            .len()
+            .checked_sub(age + 1)
+            .and_then(|i| self.queries.get(i))
+            .map(String::as_str)
+    }
+
+    /// Queries from the latest to the oldest, along with their age.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &str)> {
+        self.queries.iter().rev().map(String::as_str).enumerate()
+    }
+
+    /// Remembers `query`, moving it to the front if it was submitted before.
+    pub fn push(&mut self, query: &str) {
+        if self.insert(query) {
+            super::save(FILE, self);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.queries.clear();
+        super::remove(FILE);
+    }
+
+    /// Removes the stored history without loading it.
+    pub fn clear_stored() {
+        super::remove(FILE);
+    }
+
+    /// Adds `query` as the latest one, returning whether it was not blank.
+    fn insert(&mut self, query: &str) -> bool {
+        let query = query.trim();
+        if query.is_empty() {
+            return false;
+        }
+        self.queries.retain(|q| q != query);
+        if self.queries.len() >= MAX_QUERIES {
+            self.queries.drain(..=self.queries.len() - MAX_QUERIES);
+        }
+        self.queries.push(query.to_string());
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history(queries: &[&str]) -> QueryHistory {
+        let mut history = QueryHistory::default();
+        for query in queries {
+            history.insert(query);
+        }
+        history
+    }
+
+    fn latest_first(history: &QueryHistory) -> Vec<&str> {
+        history.iter().map(|(_, query)| query).collect()
+    }
+
+    #[test]
+    fn latest_first_by_age() {
+        let history = history(&["firefox", "files", "terminal"]);
+        assert_eq!(latest_first(&history), ["terminal", "files", "firefox"]);
+        assert_eq!(history.get(0), Some("terminal"));
+        assert_eq!(history.get(2), Some("firefox"));
+        assert_eq!(history.get(3), None);
+        let ages: Vec<usize> = history.iter().map(|(age, _)| age).collect();
+        assert_eq!(ages, [0, 1, 2]);
+    }
+
+    #[test]
+    fn dedup_and_move_to_front() {
+        let history = history(&["firefox", "files", "firefox", "terminal", " files "]);
+        assert_eq!(latest_first(&history), ["files", "terminal", "firefox"]);
+    }
+
+    #[test]
+    fn blank_queries() {
+        let mut history = history(&["firefox"]);
+        assert!(!history.insert(""));
+        assert!(!history.insert("   "));
+        assert_eq!(latest_first(&history), ["firefox"]);
+    }
+
+    #[test]
+    fn capacity() {
+        let queries: Vec<String> = (0..MAX_QUERIES + 5).map(|i| format!("query {i}")).collect();
+        let mut history = QueryHistory::default();
+        for query in &queries {
+            history.insert(query);
+        }
+        assert_eq!(history.iter().count(), MAX_QUERIES);
+        assert_eq!(history.get(0), Some(queries[MAX_QUERIES + 4].as_str()));
+        assert_eq!(history.get(MAX_QUERIES - 1), Some("query 5"));
+
+        // reusing a query makes room without dropping another one
+        history.insert("query 5");
+        assert_eq!(history.iter().count(), MAX_QUERIES);
+        assert_eq!(history.get(0), Some("query 5"));
+        assert_eq!(history.get(MAX_QUERIES - 1), Some("query 6"));
+    }
+}
@@ -0,0 +1,45 @@
+use serde::{Deserialize, Serialize};
+
+use crate::emoji::SkinTone;
+
+const FILE: &str = "recent-emoji.json";
+/// Oldest emoji are dropped once more than this were picked.
+const MAX_EMOJI: usize = 24;
+
+/// Emoji picked recently, oldest first and without skin tone, along with
+/// the skin tone chosen in the picker.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RecentEmoji {
+    glyphs: Vec<String>,
+    skin_tone: SkinTone,
+}
+
+impl RecentEmoji {
+    pub fn load() -> Self {
+        super::load(FILE)
+    }
+
+    /// Recent emoji from the latest to the oldest.
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.glyphs.iter().rev().map(String::as_str)
+    }
+
+    pub fn push(&mut self, glyph: &str) {
+        self.glyphs.retain(|g| g != glyph);
+        if self.glyphs.len() >= MAX_EMOJI {
+            self.glyphs.drain(..=self.glyphs.len() - MAX_EMOJI);
+        }
+        self.glyphs.push(glyph.to_string());
+        super::save(FILE, self);
+    }
+
+    pub fn skin_tone(&self) -> SkinTone {
+        self.skin_tone
+    }
+
+    pub fn set_skin_tone(&mut self, skin_tone: SkinTone) {
+        self.skin_tone = skin_tone;
+        super::save(FILE, self);
+    }
+}
@@ -0,0 +1,101 @@
+use std::hash::Hash;
+use std::thread;
+
+use iced::subscription;
+use tokio::sync::mpsc;
+
+use crate::clipboard::TEXT_TYPES;
+use crate::data_control::Watcher;
+
+/// New clipboard contents.
+#[derive(Debug, Clone)]
+pub enum ClipboardEvent {
+    Text(String),
+    Image { mime: String, data: Vec<u8> },
+}
+
+/// Watches the clipboard for changes.
+///
+/// The selection is followed through the ext-data-control or
+/// wlr-data-control protocol, which unlike the data-device protocol does
+/// not require keyboard focus. Contents offering any of the `excluded` MIME
+/// types, as password managers do, are skipped.
+pub fn clipboard_watcher<I: 'static + Hash + Copy + Send + Sync>(
+    id: I,
+    excluded: Vec<String>,
+) -> iced::Subscription<(I, ClipboardEvent)> {
+    subscription::unfold(id, State::Ready(excluded), move |state| watch(id, state))
+}
+
+pub enum State {
+    Ready(Vec<String>),
+    Watching(mpsc::UnboundedReceiver<ClipboardEvent>),
+    Finished,
+}
+
+async fn watch<I: Copy>(id: I, state: State) -> (Option<(I, ClipboardEvent)>, State) {
+    match state {
+        State::Ready(excluded) => {
+            // the protocol objects block on a connection of their own, so
+            // they are kept on a thread rather than in the runtime
+            let (tx, rx) = mpsc::unbounded_channel();
+            let spawned = thread::Builder::new()
+                .name("clipboard-watcher".to_string())
+                .spawn(move || match Watcher::new() {
+                    Ok(watcher) => follow(watcher, &excluded, &tx),
+                    Err(err) => {
+                        log::warn!("clipboard history is disabled: {err:#}");
+                    }
+                });
+            match spawned {
+                Ok(_) => (None, State::Watching(rx)),
+                Err(err) => {
+                    log::error!("failed to watch the clipboard: {err}");
+                    (None, State::Finished)
+                }
+            }
+        }
+        State::Watching(mut rx) => match rx.recv().await {
+            Some(event) => (Some((id, event)), State::Watching(rx)),
+            None => (None, State::Finished),
+        },
+        State::Finished => iced::futures::future::pending().await,
+    }
+}
+
+/// Sends the contents of the clipboard each time it changes, until the
+/// subscription is dropped.
+fn follow(mut watcher: Watcher, excluded: &[String], tx: &mpsc::UnboundedSender<ClipboardEvent>) {
+    while !tx.is_closed() {
+        let types = match watcher.changed() {
+            Ok(types) => types,
+            Err(err) => {
+                log::warn!("stopped watching the clipboard: {err:#}");
+                return;
+            }
+        };
+        if types.iter().any(|t| excluded.contains(t)) {
+            continue;
+        }
+        if let Some(event) = read(&watcher, &types) {
+            let _ = tx.send(event);
+        }
+    }
+}
+
+/// Reads the current clipboard contents, preferring images over text.
+fn read(watcher: &Watcher, types: &[String]) -> Option<ClipboardEvent> {
+    if let Some(mime) = types.iter().find(|t| t.starts_with("image/")) {
+        let data = watcher.read(mime)?;
+        return Some(ClipboardEvent::Image {
+            mime: mime.clone(),
+            data,
+        });
+    }
+
+    let mime = TEXT_TYPES
+        .iter()
+        .find(|mime| types.iter().any(|t| t == *mime))?;
+    let data = watcher.read(mime)?;
+    String::from_utf8(data).ok().map(ClipboardEvent::Text)
+}
@@ -1,18 +1,53 @@
 use cosmic::iced::futures::{channel::mpsc, StreamExt};
-use futures::Stream;
-use pop_launcher::{Request, Response};
-use std::{hash::Hash, pin::Pin};
+use pop_launcher::{IconSource, Request, Response, SearchResult};
+use pop_launcher_service::IpcClient;
+use std::hash::Hash;
+use std::path::PathBuf;
+use tokio::task::JoinHandle;
+
+use crate::preferences::Preferences;
+use crate::providers::{self, Action, Origin, Provider, ProviderResult};
+use crate::uri;
 
 #[derive(Debug, Clone)]
 pub enum LauncherRequest {
     Search(String),
-    Activate(u32),
+    /// Searches with the local provider of the given name only, for a query
+    /// typed after one of its prefixes.
+    SearchIn(String, String),
+    Activate(Origin, u32),
+    /// Copies the text of a result to the clipboard.
+    Copy(Origin, u32),
+}
+
+/// A search result tagged with where it came from.
+#[derive(Debug, Clone)]
+pub struct LauncherItem {
+    pub origin: Origin,
+    pub result: SearchResult,
+    /// Context options of a local result.
+    pub options: Vec<(String, Action)>,
+    /// The URI of the file the result stands for, if any.
+    pub uri: Option<String>,
+    /// Information of a local result for the preview pane.
+    pub details: Vec<(String, String)>,
 }
 
 #[derive(Debug, Clone)]
 pub enum LauncherEvent {
     Started(mpsc::Sender<LauncherRequest>),
+    /// A response of the service, other than its search results.
     Response(pop_launcher::Response),
+    /// The merged results of the service and the local providers for
+    /// `query`. `answered` is set once the service replied to the search of
+    /// `query` itself, or if it is not searched.
+    Update {
+        query: String,
+        items: Vec<LauncherItem>,
+        answered: bool,
+    },
+    /// A local result was activated, or a result is to be copied.
+    Action(Action),
     Error(String),
 }
 
@@ -27,32 +62,23 @@ pub fn launcher<I: 'static + Hash + Copy + Send + Sync>(
 async fn _launcher<I: Copy>(id: I, state: State) -> (Option<(I, LauncherEvent)>, State) {
     match state {
         State::Ready => {
-            if let Ok(launcher_ipc) = LauncherIpc::new() {
-                (
-                    Some((id, LauncherEvent::Started(launcher_ipc.get_sender()))),
-                    State::Waiting(launcher_ipc),
-                )
-            } else {
-                (
-                    Some((
-                        id,
-                        LauncherEvent::Error("Failed to start the ipc client".to_string()),
-                    )),
-                    State::Error,
-                )
-            }
+            let providers = providers::enabled(&Preferences::load());
+            let aggregator = Aggregator::new(providers);
+            (
+                Some((id, LauncherEvent::Started(aggregator.get_sender()))),
+                State::Waiting(aggregator),
+            )
         }
         State::Waiting(mut rx) => {
-            if let Some(response) = rx.results().await {
-                (
-                    Some((id, LauncherEvent::Response(response))),
-                    State::Waiting(rx),
-                )
+            if let Some(event) = rx.events().await {
+                (Some((id, event)), State::Waiting(rx))
             } else {
                 (
                     Some((
                         id,
-                        LauncherEvent::Error("channel for ipc client was closed".to_string()),
+                        LauncherEvent::Error(
+                            "channel for result aggregator was closed".to_string(),
+                        ),
                     )),
                     State::Error,
                 )
@@ -64,42 +90,361 @@ async fn _launcher<I: Copy>(id: I, state: State) -> (Option<(I, LauncherEvent)>,
 
 pub enum State {
     Ready,
-    Waiting(LauncherIpc),
+    Waiting(Aggregator),
     Error,
 }
 
-pub struct LauncherIpc {
-    ipc_rx: Pin<Box<dyn Stream<Item = Response> + Send>>,
+enum Internal {
+    Request(LauncherRequest),
+    Service(Response),
+    Local {
+        provider: usize,
+        generation: u64,
+        results: Vec<ProviderResult>,
+    },
+}
+
+/// Sends searches to the pop-launcher service and the local providers, and
+/// merges their results.
+///
+/// The launcher keeps working with the local providers only if the service
+/// is not installed.
+pub struct Aggregator {
+    events: mpsc::UnboundedReceiver<LauncherEvent>,
     tx: mpsc::Sender<LauncherRequest>,
 }
 
-impl LauncherIpc {
-    pub fn new() -> anyhow::Result<Self> {
-        let (mut ipc_tx, ipc_rx) = pop_launcher_service::IpcClient::new()?;
+impl Aggregator {
+    pub fn new(providers: Vec<Box<dyn Provider>>) -> Self {
         let (tx, mut rx) = mpsc::channel(100);
+        let (events_tx, events) = mpsc::unbounded();
+        let (internal_tx, internal_rx) = mpsc::unbounded();
+
+        let requests = internal_tx.clone();
         tokio::spawn(async move {
             while let Some(req) = rx.next().await {
-                match req {
-                    LauncherRequest::Search(s) => {
-                        let _ = ipc_tx.send(Request::Search(s)).await;
-                    }
-                    LauncherRequest::Activate(i) => {
-                        let _ = ipc_tx.send(Request::Activate(i)).await;
-                    }
+                if requests.unbounded_send(Internal::Request(req)).is_err() {
+                    break;
                 }
             }
         });
-        Ok(Self {
-            ipc_rx: Box::pin(ipc_rx),
-            tx,
-        })
+
+        let service = match IpcClient::new() {
+            Ok((ipc_tx, ipc_rx)) => {
+                let responses = internal_tx.clone();
+                tokio::spawn(async move {
+                    let mut ipc_rx = Box::pin(ipc_rx);
+                    while let Some(response) = ipc_rx.next().await {
+                        if responses
+                            .unbounded_send(Internal::Service(response))
+                            .is_err()
+                        {
+                            break;
+                        }
+                    }
+                });
+                Some(ipc_tx)
+            }
+            Err(err) => {
+                log::warn!("failed to start the ipc client, using local providers only: {err}");
+                None
+            }
+        };
+
+        let results = Results {
+            service,
+            local: vec![Vec::new(); providers.len()],
+            providers,
+            service_results: Vec::new(),
+            searches: Vec::new(),
+            generation: 0,
+            scope: None,
+            query: String::new(),
+            service_busy: false,
+            queued: None,
+        };
+        tokio::spawn(results.run(internal_tx, internal_rx, events_tx));
+
+        Self { events, tx }
     }
 
     pub fn get_sender(&self) -> mpsc::Sender<LauncherRequest> {
         self.tx.clone()
     }
 
-    pub async fn results(&mut self) -> Option<Response> {
-        self.ipc_rx.next().await
+    pub async fn events(&mut self) -> Option<LauncherEvent> {
+        self.events.next().await
+    }
+}
+
+/// State of the aggregator task.
+struct Results {
+    service: Option<IpcClient>,
+    providers: Vec<Box<dyn Provider>>,
+    service_results: Vec<SearchResult>,
+    local: Vec<Vec<ProviderResult>>,
+    /// Running provider searches, aborted when the query changes.
+    searches: Vec<JoinHandle<()>>,
+    generation: u64,
+    /// The provider searched alone, if the query was typed after a prefix.
+    scope: Option<String>,
+    query: String,
+    /// Set from sending a search to the service until it replies. Its
+    /// replies are not tagged with their search, so one is sent at a time.
+    service_busy: bool,
+    /// The latest query, kept until the service replied to the search
+    /// before it.
+    queued: Option<String>,
+}
+
+impl Results {
+    async fn run(
+        mut self,
+        internal_tx: mpsc::UnboundedSender<Internal>,
+        mut internal_rx: mpsc::UnboundedReceiver<Internal>,
+        events: mpsc::UnboundedSender<LauncherEvent>,
+    ) {
+        while let Some(msg) = internal_rx.next().await {
+            let event = match msg {
+                Internal::Request(LauncherRequest::Search(query)) => {
+                    self.search(query, None, &internal_tx).await;
+                    None
+                }
+                Internal::Request(LauncherRequest::SearchIn(provider, query)) => {
+                    self.search(query, Some(provider), &internal_tx).await;
+                    Some(self.update())
+                }
+                Internal::Request(LauncherRequest::Activate(Origin::Service, id)) => {
+                    self.send(Request::Activate(id)).await;
+                    None
+                }
+                // results of a search that has been replaced since, whose ids
+                // may stand for other results now
+                Internal::Request(
+                    LauncherRequest::Activate(Origin::Local { generation, .. }, id)
+                    | LauncherRequest::Copy(Origin::Local { generation, .. }, id),
+                ) if generation != self.generation => {
+                    log::debug!("ignoring result {id} of a previous search");
+                    None
+                }
+                Internal::Request(LauncherRequest::Activate(
+                    Origin::Local { provider: i, .. },
+                    id,
+                )) => {
+                    match (
+                        self.providers.get_mut(i),
+                        self.local.get(i).and_then(|r| r.get(id as usize)),
+                    ) {
+                        (Some(provider), Some(result)) => {
+                            provider.activate(result).map(LauncherEvent::Action)
+                        }
+                        _ => None,
+                    }
+                }
+                Internal::Request(LauncherRequest::Copy(Origin::Service, id)) => self
+                    .service_results
+                    .iter()
+                    .find(|result| result.id == id)
+                    .map(|result| LauncherEvent::Action(Action::Copy(copied(result)))),
+                Internal::Request(LauncherRequest::Copy(Origin::Local { provider: i, .. }, id)) => {
+                    match (
+                        self.providers.get(i),
+                        self.local.get(i).and_then(|r| r.get(id as usize)),
+                    ) {
+                        (Some(provider), Some(result)) => {
+                            Some(LauncherEvent::Action(Action::Copy(provider.copy(result))))
+                        }
+                        _ => None,
+                    }
+                }
+                Internal::Service(Response::Update(results)) => {
+                    self.service_busy = false;
+                    match self.queued.take() {
+                        // the reply to a search that has been replaced since
+                        Some(query) => {
+                            self.search_service(query).await;
+                            None
+                        }
+                        // results of the service for the query before the prefix
+                        None if self.scope.is_some() => None,
+                        None => {
+                            self.service_results = results;
+                            Some(self.update())
+                        }
+                    }
+                }
+                Internal::Service(response) => Some(LauncherEvent::Response(response)),
+                Internal::Local {
+                    provider,
+                    generation,
+                    results,
+                } if generation == self.generation => {
+                    self.local[provider] = results;
+                    Some(self.update())
+                }
+                // results of a search that has been replaced since
+                Internal::Local { .. } => None,
+            };
+
+            if let Some(event) = event {
+                if events.unbounded_send(event).is_err() {
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Searches `query` with every provider and the service, or with the
+    /// provider named `scope` only.
+    async fn search(
+        &mut self,
+        query: String,
+        scope: Option<String>,
+        internal_tx: &mpsc::UnboundedSender<Internal>,
+    ) {
+        self.generation += 1;
+        for search in self.searches.drain(..) {
+            search.abort();
+        }
+        for results in &mut self.local {
+            results.clear();
+        }
+        // their ids belong to the previous query
+        self.service_results.clear();
+
+        for (provider, p) in self.providers.iter_mut().enumerate() {
+            let mut stream = match scope.as_deref() {
+                None => p.search(&query),
+                Some(name) if name == p.name() => p.search_prefixed(&query),
+                Some(_) => continue,
+            };
+            let tx = internal_tx.clone();
+            let generation = self.generation;
+            self.searches.push(tokio::spawn(async move {
+                while let Some(results) = stream.next().await {
+                    let msg = Internal::Local {
+                        provider,
+                        generation,
+                        results,
+                    };
+                    if tx.unbounded_send(msg).is_err() {
+                        break;
+                    }
+                }
+            }));
+        }
+
+        self.scope = scope;
+        self.query = query.clone();
+        self.queued = None;
+        if self.scope.is_some() {
+            return;
+        }
+        if self.service_busy {
+            self.queued = Some(query);
+        } else {
+            self.search_service(query).await;
+        }
+    }
+
+    async fn search_service(&mut self, query: String) {
+        self.service_busy = self.send(Request::Search(query)).await;
+    }
+
+    fn update(&self) -> LauncherEvent {
+        LauncherEvent::Update {
+            query: self.query.clone(),
+            items: self.merged(),
+            answered: self.scope.is_some() || !self.service_busy,
+        }
+    }
+
+    /// Sends `request` to the service, if it is running, and returns whether
+    /// it was sent.
+    async fn send(&mut self, request: Request) -> bool {
+        let service = match self.service.as_mut() {
+            Some(service) => service,
+            None => return false,
+        };
+        match service.send(request).await {
+            Ok(_) => true,
+            Err(err) => {
+                log::error!("failed to send request to the launcher service: {err}");
+                false
+            }
+        }
+    }
+
+    /// Local results first, in the order of the providers, then the results
+    /// of the service.
+    fn merged(&self) -> Vec<LauncherItem> {
+        let generation = self.generation;
+        let local = self
+            .local
+            .iter()
+            .enumerate()
+            .flat_map(|(provider, results)| {
+                results.iter().enumerate().map(move |(id, r)| {
+                    let mut result = r.result.clone();
+                    result.id = id as u32;
+                    LauncherItem {
+                        origin: Origin::Local {
+                            provider,
+                            generation,
+                        },
+                        result,
+                        options: r.options.clone(),
+                        uri: match &r.action {
+                            Action::Open { uri, .. } => Some(uri.clone()),
+                            _ => None,
+                        },
+                        details: r.details.clone(),
+                    }
+                })
+            });
+        let service = self.service_results.iter().map(|result| LauncherItem {
+            origin: Origin::Service,
+            result: result.clone(),
+            options: Vec::new(),
+            uri: file_uri(result),
+            details: Vec::new(),
+        });
+        local.chain(service).collect()
+    }
+}
+
+/// The file a result of the service stands for, going by the MIME type icon
+/// and the path in the description that the file search plugins list.
+fn file_uri(result: &SearchResult) -> Option<String> {
+    if !matches!(result.icon, Some(IconSource::Mime(_))) {
+        return None;
+    }
+    let path = match result.description.strip_prefix("~/") {
+        Some(rest) => PathBuf::from(std::env::var_os("HOME")?).join(rest),
+        None => PathBuf::from(&result.description),
+    };
+    if !path.is_absolute() {
+        return None;
+    }
+    // the description is either the path of the file or of its directory
+    let path = if path.ends_with(&result.name) {
+        path
+    } else {
+        path.join(&result.name)
+    };
+    Some(uri::from_path(&path))
+}
+
+/// The text copied to the clipboard for a result of the service: the path of
+/// a file, and otherwise its name followed by its description, as plugins
+/// like the calculator put the value in either.
+fn copied(result: &SearchResult) -> String {
+    if let Some(path) = file_uri(result).as_deref().and_then(uri::to_path) {
+        return path.to_string_lossy().into_owned();
+    }
+    if result.description.is_empty() {
+        result.name.clone()
+    } else {
+        format!("{}\n{}", result.name, result.description)
     }
 }
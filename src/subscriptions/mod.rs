@@ -1,2 +1,4 @@
+pub mod clipboard;
 pub mod launcher;
 pub mod toggle_dbus;
+pub mod toplevels;
@@ -6,6 +6,9 @@ use iced::subscription;
 use std::{fmt::Debug, hash::Hash};
 use zbus::{dbus_interface, Connection, ConnectionBuilder};
 
+pub const OBJECT_PATH: &str = "/com/system76/IcedLauncher";
+pub const INTERFACE: &str = "com.system76.IcedLauncher";
+
 pub fn dbus_toggle<I: 'static + Hash + Copy + Send + Sync + Debug>(
     id: I,
 ) -> iced::Subscription<(I, LauncherDbusEvent)> {
@@ -26,10 +29,7 @@ async fn start_listening<I: Copy>(id: I, state: State) -> (Option<(I, LauncherDb
             if let Some(conn) = ConnectionBuilder::session()
                 .ok()
                 .and_then(|conn| conn.name(crate::config::APP_ID).ok())
-                .and_then(|conn| {
-                    conn.serve_at("/com/system76/IcedLauncher", IcedLauncherServer { tx })
-                        .ok()
-                })
+                .and_then(|conn| conn.serve_at(OBJECT_PATH, IcedLauncherServer { tx }).ok())
                 .map(|conn| conn.build())
             {
                 if let Ok(conn) = conn.await {
@@ -39,11 +39,8 @@ async fn start_listening<I: Copy>(id: I, state: State) -> (Option<(I, LauncherDb
             return (None, State::Finished);
         }
         State::Waiting(conn, mut rx) => {
-            if let Some(LauncherDbusEvent::Toggle) = rx.next().await {
-                (
-                    Some((id, LauncherDbusEvent::Toggle)),
-                    State::Waiting(conn, rx),
-                )
+            if let Some(event) = rx.next().await {
+                (Some((id, event)), State::Waiting(conn, rx))
             } else {
                 (None, State::Finished)
             }
@@ -52,9 +49,13 @@ async fn start_listening<I: Copy>(id: I, state: State) -> (Option<(I, LauncherDb
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub enum LauncherDbusEvent {
     Toggle,
+    /// Show the launcher in the named mode.
+    Open(String),
+    ClearHistory,
+    ClearQueryHistory,
 }
 
 #[derive(Debug)]
@@ -67,4 +68,22 @@ impl IcedLauncherServer {
     async fn toggle(&self) {
         self.tx.unbounded_send(LauncherDbusEvent::Toggle).unwrap();
     }
+
+    async fn open(&self, mode: String) {
+        self.tx
+            .unbounded_send(LauncherDbusEvent::Open(mode))
+            .unwrap();
+    }
+
+    async fn clear_history(&self) {
+        self.tx
+            .unbounded_send(LauncherDbusEvent::ClearHistory)
+            .unwrap();
+    }
+
+    async fn clear_query_history(&self) {
+        self.tx
+            .unbounded_send(LauncherDbusEvent::ClearQueryHistory)
+            .unwrap();
+    }
 }
@@ -0,0 +1,77 @@
+use std::hash::Hash;
+use std::thread;
+
+use iced::subscription;
+use tokio::sync::mpsc;
+
+use crate::toplevels::{Controller, Toplevels, Window};
+
+#[derive(Debug, Clone)]
+pub enum ToplevelEvent {
+    Started(Controller),
+    /// The open windows, the one activated last first.
+    Update(Vec<Window>),
+}
+
+/// Follows the open windows for the window switcher.
+pub fn toplevels<I: 'static + Hash + Copy + Send + Sync>(
+    id: I,
+) -> iced::Subscription<(I, ToplevelEvent)> {
+    subscription::unfold(id, State::Ready, move |state| watch(id, state))
+}
+
+pub enum State {
+    Ready,
+    Watching(mpsc::UnboundedReceiver<Vec<Window>>),
+    Finished,
+}
+
+async fn watch<I: Copy>(id: I, state: State) -> (Option<(I, ToplevelEvent)>, State) {
+    match state {
+        State::Ready => {
+            let (mut toplevels, controller) = match Toplevels::new() {
+                Ok(toplevels) => toplevels,
+                Err(err) => {
+                    log::warn!("the window switcher is disabled: {err:#}");
+                    return (None, State::Finished);
+                }
+            };
+            // the protocol objects block on a connection of their own, so
+            // they are kept on a thread rather than in the runtime
+            let (tx, rx) = mpsc::unbounded_channel();
+            let spawned = thread::Builder::new()
+                .name("toplevels".to_string())
+                .spawn(move || {
+                    while !tx.is_closed() {
+                        match toplevels.changed() {
+                            Ok(windows) => {
+                                let _ = tx.send(windows);
+                            }
+                            Err(err) => {
+                                log::warn!("stopped following the windows: {err:#}");
+                                return;
+                            }
+                        }
+                    }
+                });
+            match spawned {
+                Ok(_) => (
+                    Some((id, ToplevelEvent::Started(controller))),
+                    State::Watching(rx),
+                ),
+                Err(err) => {
+                    log::error!("failed to follow the windows: {err}");
+                    (None, State::Finished)
+                }
+            }
+        }
+        State::Watching(mut rx) => match rx.recv().await {
+            Some(windows) => (
+                Some((id, ToplevelEvent::Update(windows))),
+                State::Watching(rx),
+            ),
+            None => (None, State::Finished),
+        },
+        State::Finished => iced::futures::future::pending().await,
+    }
+}
@@ -0,0 +1,138 @@
+//! Thumbnails of files, following the freedesktop thumbnail managing
+//! standard.
+//!
+//! Thumbnails are looked up in `$XDG_CACHE_HOME/thumbnails` by the MD5 of
+//! the file URI, and those that are missing or outdated are asked of the
+//! `org.freedesktop.thumbnails.Thumbnailer1` service, which writes them to
+//! the same cache.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use futures::StreamExt;
+use tokio::sync::OnceCell;
+use zbus::{Connection, Proxy};
+
+const DIR: &str = "thumbnails";
+/// Sizes looked up, from the preferred one. The service is asked for the
+/// first one.
+const FLAVORS: &[&str] = &["large", "x-large", "xx-large", "normal"];
+/// How long the service may take to write a thumbnail.
+const GENERATE_TIMEOUT: Duration = Duration::from_secs(10);
+const PNG_SIGNATURE: &[u8] = b"\x89PNG\r\n\x1a\n";
+
+static CONNECTION: OnceCell<Option<Connection>> = OnceCell::const_new();
+
+/// The thumbnail of the file at `uri`, last modified at `modified`, asking
+/// the thumbnailer service for one if it is missing.
+pub async fn thumbnail(uri: String, mime: String, modified: SystemTime) -> Option<PathBuf> {
+    let dir = xdg::BaseDirectories::new().ok()?.get_cache_home().join(DIR);
+    let name = format!("{:x}.png", md5::compute(&uri));
+    let mtime = mtime(modified);
+    let lookup = {
+        let (dir, name) = (dir.clone(), name.clone());
+        move || (find(&dir, &name, mtime), has_failed(&dir, &name))
+    };
+    match tokio::task::spawn_blocking(lookup).await.ok()? {
+        (Some(path), _) => return Some(path),
+        // thumbnailers that failed on the file leave a mark
+        (None, true) => return None,
+        (None, false) => {}
+    }
+    if let Err(err) = generate(&uri, &mime).await {
+        log::debug!("failed to generate a thumbnail of {uri}: {err}");
+        return None;
+    }
+    tokio::task::spawn_blocking(move || find(&dir, &name, mtime))
+        .await
+        .ok()?
+}
+
+/// The thumbnail named `name` of a file last modified at `mtime`, skipping
+/// the outdated ones.
+fn find(dir: &Path, name: &str, mtime: Option<u64>) -> Option<PathBuf> {
+    FLAVORS
+        .iter()
+        .map(|flavor| dir.join(flavor).join(name))
+        .find(|path| match (thumb_mtime(path), mtime) {
+            (Some(thumb), Some(file)) => thumb == file,
+            // thumbnails without the time of the file are taken as is
+            _ => path.exists(),
+        })
+}
+
+fn has_failed(dir: &Path, name: &str) -> bool {
+    let entries = match fs::read_dir(dir.join("fail")) {
+        Ok(entries) => entries,
+        Err(_) => return false,
+    };
+    entries
+        .flatten()
+        .any(|thumbnailer| thumbnailer.path().join(name).exists())
+}
+
+fn mtime(time: SystemTime) -> Option<u64> {
+    time.duration_since(UNIX_EPOCH).ok().map(|d| d.as_secs())
+}
+
+/// The `Thumb::MTime` text of the PNG at `path`, the modification time of
+/// the file it was made from.
+fn thumb_mtime(path: &Path) -> Option<u64> {
+    let data = fs::read(path).ok()?;
+    let mut rest = data.strip_prefix(PNG_SIGNATURE)?;
+    // chunks are a length, a type, the data and a checksum
+    while rest.len() >= 12 {
+        let len = u32::from_be_bytes(rest[..4].try_into().ok()?) as usize;
+        let kind = &rest[4..8];
+        let chunk = rest.get(8..8 + len)?;
+        match kind {
+            b"tEXt" => {
+                if let Some(value) = chunk.strip_prefix(b"Thumb::MTime\0") {
+                    return std::str::from_utf8(value).ok()?.trim().parse().ok();
+                }
+            }
+            // the text chunks of thumbnails come before the image data
+            b"IDAT" | b"IEND" => return None,
+            _ => {}
+        }
+        rest = rest.get(8 + len + 4..)?;
+    }
+    None
+}
+
+/// Asks the thumbnailer service for a thumbnail of `uri`, and waits until
+/// it is done.
+async fn generate(uri: &str, mime: &str) -> zbus::Result<()> {
+    let conn = CONNECTION
+        .get_or_init(|| async { Connection::session().await.ok() })
+        .await
+        .clone()
+        .ok_or_else(|| zbus::Error::Failure("no session bus".to_string()))?;
+    let proxy = Proxy::new(
+        &conn,
+        "org.freedesktop.thumbnails.Thumbnailer1",
+        "/org/freedesktop/thumbnails/Thumbnailer1",
+        "org.freedesktop.thumbnails.Thumbnailer1",
+    )
+    .await?;
+    // listening before queueing, so the signal cannot be missed
+    let mut finished = proxy.receive_signal("Finished").await?;
+    let handle: u32 = proxy
+        .call(
+            "Queue",
+            &(&[uri][..], &[mime][..], FLAVORS[0], "default", 0u32),
+        )
+        .await?;
+
+    let wait = async {
+        while let Some(signal) = finished.next().await {
+            if signal.body::<u32>().ok() == Some(handle) {
+                return;
+            }
+        }
+    };
+    tokio::time::timeout(GENERATE_TIMEOUT, wait)
+        .await
+        .map_err(|_| zbus::Error::Failure("timed out".to_string()))
+}
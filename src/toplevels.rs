@@ -0,0 +1,330 @@
+//! Open windows, followed through the cosmic-toplevel-info protocol and
+//! activated or closed through cosmic-toplevel-management.
+//!
+//! Like the clipboard, windows are followed over a connection of their own,
+//! so the list is kept while the launcher surface is closed and it knows
+//! which window was used last when it opens.
+
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Context};
+use cosmic_protocols::toplevel_info::v1::client::{
+    zcosmic_toplevel_handle_v1::{self, ZcosmicToplevelHandleV1},
+    zcosmic_toplevel_info_v1::{self, ZcosmicToplevelInfoV1},
+};
+use cosmic_protocols::toplevel_management::v1::client::zcosmic_toplevel_manager_v1::ZcosmicToplevelManagerV1;
+use cosmic_protocols::workspace::v1::client::{
+    zcosmic_workspace_group_handle_v1::{self, ZcosmicWorkspaceGroupHandleV1},
+    zcosmic_workspace_handle_v1::{self, ZcosmicWorkspaceHandleV1},
+    zcosmic_workspace_manager_v1::{self, ZcosmicWorkspaceManagerV1},
+};
+use wayland_client::backend::ObjectId;
+use wayland_client::globals::{registry_queue_init, GlobalListContents};
+use wayland_client::protocol::{wl_registry, wl_seat};
+use wayland_client::{
+    delegate_noop, event_created_child, Connection, Dispatch, EventQueue, Proxy, QueueHandle,
+};
+
+/// Value of the activated state in the `state` event of a toplevel.
+const ACTIVATED: u32 = 2;
+
+/// An open window.
+#[derive(Debug, Clone)]
+pub struct Window {
+    handle: ZcosmicToplevelHandleV1,
+    pub title: String,
+    pub app_id: String,
+    /// Names of the workspaces the window is on.
+    pub workspaces: Vec<String>,
+}
+
+/// Activates and closes windows.
+#[derive(Debug, Clone)]
+pub struct Controller {
+    conn: Connection,
+    manager: ZcosmicToplevelManagerV1,
+    seat: wl_seat::WlSeat,
+}
+
+impl Controller {
+    pub fn activate(&self, window: &Window) {
+        self.manager.activate(&window.handle, &self.seat);
+        self.flush();
+    }
+
+    /// Asks the window to close, which it may refuse or confirm first.
+    pub fn close(&self, window: &Window) {
+        self.manager.close(&window.handle);
+        self.flush();
+    }
+
+    fn flush(&self) {
+        if let Err(err) = self.conn.flush() {
+            log::error!("failed to send a window request: {err}");
+        }
+    }
+}
+
+/// Follows the open windows.
+pub struct Toplevels {
+    queue: EventQueue<State>,
+    state: State,
+    _info: ZcosmicToplevelInfoV1,
+    _workspaces: Option<ZcosmicWorkspaceManagerV1>,
+}
+
+impl Toplevels {
+    pub fn new() -> anyhow::Result<(Self, Controller)> {
+        let conn = Connection::connect_to_env().context("no Wayland display")?;
+        let (globals, queue) = registry_queue_init::<State>(&conn)?;
+        let qh = queue.handle();
+        // bound first, so that the workspaces are known when the windows
+        // enter them, and left out of the list if they are not shared
+        let workspaces = globals.bind(&qh, 1..=1, ()).ok();
+        let info: ZcosmicToplevelInfoV1 = globals
+            .bind(&qh, 1..=1, ())
+            .context("the compositor does not list its windows")?;
+        let manager = globals
+            .bind(&qh, 1..=1, ())
+            .context("the compositor does not manage its windows")?;
+        let seat = globals.bind(&qh, 1..=1, ()).context("no seat")?;
+        let toplevels = Self {
+            queue,
+            state: State::default(),
+            _info: info,
+            _workspaces: workspaces,
+        };
+        Ok((
+            toplevels,
+            Controller {
+                conn,
+                manager,
+                seat,
+            },
+        ))
+    }
+
+    /// Blocks until windows were opened, closed or changed, and returns
+    /// them, the one activated last first.
+    pub fn changed(&mut self) -> anyhow::Result<Vec<Window>> {
+        while !self.state.changed {
+            if self.state.finished {
+                return Err(anyhow!("the compositor stopped listing its windows"));
+            }
+            self.queue.blocking_dispatch(&mut self.state)?;
+        }
+        self.state.changed = false;
+        Ok(self.state.windows())
+    }
+}
+
+#[derive(Default)]
+struct State {
+    /// In the order they were opened.
+    toplevels: Vec<Toplevel>,
+    /// Names of the workspaces, by the ID of their handle.
+    workspaces: HashMap<ObjectId, String>,
+    /// Activations so far, which orders windows by the last one.
+    activations: u64,
+    /// Set when the windows change, until the watcher notices.
+    changed: bool,
+    finished: bool,
+}
+
+struct Toplevel {
+    handle: ZcosmicToplevelHandleV1,
+    title: String,
+    app_id: String,
+    workspaces: Vec<ObjectId>,
+    activated: bool,
+    /// Value of `State::activations` when the window was last activated.
+    last_activated: u64,
+    /// Set once the compositor sent the first batch of its properties.
+    ready: bool,
+}
+
+impl State {
+    fn windows(&self) -> Vec<Window> {
+        let mut toplevels: Vec<&Toplevel> = self.toplevels.iter().filter(|t| t.ready).collect();
+        // windows never activated last, the newest of them first
+        toplevels.reverse();
+        toplevels.sort_by_key(|t| std::cmp::Reverse(t.last_activated));
+        toplevels
+            .into_iter()
+            .map(|t| Window {
+                handle: t.handle.clone(),
+                title: t.title.clone(),
+                app_id: t.app_id.clone(),
+                workspaces: t
+                    .workspaces
+                    .iter()
+                    .filter_map(|id| self.workspaces.get(id).cloned())
+                    .collect(),
+            })
+            .collect()
+    }
+
+    fn toplevel(&mut self, handle: &ZcosmicToplevelHandleV1) -> Option<&mut Toplevel> {
+        self.toplevels.iter_mut().find(|t| &t.handle == handle)
+    }
+}
+
+impl Dispatch<wl_registry::WlRegistry, GlobalListContents> for State {
+    fn event(
+        _: &mut Self,
+        _: &wl_registry::WlRegistry,
+        _: wl_registry::Event,
+        _: &GlobalListContents,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+delegate_noop!(State: ignore wl_seat::WlSeat);
+delegate_noop!(State: ignore ZcosmicToplevelManagerV1);
+
+impl Dispatch<ZcosmicToplevelInfoV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        _: &ZcosmicToplevelInfoV1,
+        event: zcosmic_toplevel_info_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        match event {
+            zcosmic_toplevel_info_v1::Event::Toplevel { toplevel } => {
+                state.toplevels.push(Toplevel {
+                    handle: toplevel,
+                    title: String::new(),
+                    app_id: String::new(),
+                    workspaces: Vec::new(),
+                    activated: false,
+                    last_activated: 0,
+                    ready: false,
+                })
+            }
+            zcosmic_toplevel_info_v1::Event::Finished => state.finished = true,
+            _ => {}
+        }
+    }
+
+    event_created_child!(State, ZcosmicToplevelInfoV1, [
+        zcosmic_toplevel_info_v1::EVT_TOPLEVEL_OPCODE => (ZcosmicToplevelHandleV1, ()),
+    ]);
+}
+
+impl Dispatch<ZcosmicToplevelHandleV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        handle: &ZcosmicToplevelHandleV1,
+        event: zcosmic_toplevel_handle_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let zcosmic_toplevel_handle_v1::Event::Closed = event {
+            state.toplevels.retain(|t| &t.handle != handle);
+            handle.destroy();
+            state.changed = true;
+            return;
+        }
+        let activations = state.activations + 1;
+        let mut activated_now = false;
+        let toplevel = match state.toplevel(handle) {
+            Some(toplevel) => toplevel,
+            None => return,
+        };
+        match event {
+            zcosmic_toplevel_handle_v1::Event::Title { title } => toplevel.title = title,
+            zcosmic_toplevel_handle_v1::Event::AppId { app_id } => toplevel.app_id = app_id,
+            zcosmic_toplevel_handle_v1::Event::WorkspaceEnter { workspace } => {
+                toplevel.workspaces.push(workspace.id())
+            }
+            zcosmic_toplevel_handle_v1::Event::WorkspaceLeave { workspace } => {
+                toplevel.workspaces.retain(|id| *id != workspace.id())
+            }
+            zcosmic_toplevel_handle_v1::Event::State { state: states } => {
+                let activated = states
+                    .chunks_exact(4)
+                    .map(|s| u32::from_ne_bytes([s[0], s[1], s[2], s[3]]))
+                    .any(|s| s == ACTIVATED);
+                if activated && !toplevel.activated {
+                    toplevel.last_activated = activations;
+                    activated_now = true;
+                }
+                toplevel.activated = activated;
+            }
+            zcosmic_toplevel_handle_v1::Event::Done => {
+                toplevel.ready = true;
+                state.changed = true;
+            }
+            _ => {}
+        }
+        if activated_now {
+            state.activations = activations;
+        }
+    }
+}
+
+impl Dispatch<ZcosmicWorkspaceManagerV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        _: &ZcosmicWorkspaceManagerV1,
+        event: zcosmic_workspace_manager_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        // workspaces are renamed in batches, ended by `done`
+        if let zcosmic_workspace_manager_v1::Event::Done = event {
+            state.changed = true;
+        }
+    }
+
+    event_created_child!(State, ZcosmicWorkspaceManagerV1, [
+        zcosmic_workspace_manager_v1::EVT_WORKSPACE_GROUP_OPCODE => (ZcosmicWorkspaceGroupHandleV1, ()),
+    ]);
+}
+
+impl Dispatch<ZcosmicWorkspaceGroupHandleV1, ()> for State {
+    fn event(
+        _: &mut Self,
+        group: &ZcosmicWorkspaceGroupHandleV1,
+        event: zcosmic_workspace_group_handle_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let zcosmic_workspace_group_handle_v1::Event::Remove = event {
+            group.destroy();
+        }
+    }
+
+    event_created_child!(State, ZcosmicWorkspaceGroupHandleV1, [
+        zcosmic_workspace_group_handle_v1::EVT_WORKSPACE_OPCODE => (ZcosmicWorkspaceHandleV1, ()),
+    ]);
+}
+
+impl Dispatch<ZcosmicWorkspaceHandleV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        workspace: &ZcosmicWorkspaceHandleV1,
+        event: zcosmic_workspace_handle_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        match event {
+            zcosmic_workspace_handle_v1::Event::Name { name } => {
+                state.workspaces.insert(workspace.id(), name);
+            }
+            zcosmic_workspace_handle_v1::Event::Remove => {
+                state.workspaces.remove(&workspace.id());
+                workspace.destroy();
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,68 @@
+//! Local `file://` URIs, as used by recently-used.xbel and the field codes of
+//! desktop entries, and the percent-encoding of web search terms.
+
+use std::ffi::OsStr;
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+use std::path::{Path, PathBuf};
+
+/// The path of a local `file://` URI.
+pub fn to_path(uri: &str) -> Option<PathBuf> {
+    let rest = uri.strip_prefix("file://")?;
+    // only the local host is supported
+    let path = rest.strip_prefix("localhost").unwrap_or(rest);
+    if !path.starts_with('/') {
+        return None;
+    }
+    Some(PathBuf::from(std::ffi::OsString::from_vec(decode(path)?)))
+}
+
+/// The `file://` URI of an absolute path.
+pub fn from_path(path: &Path) -> String {
+    format!("file://{}", encode(path.as_os_str().as_bytes(), b"/"))
+}
+
+/// `text` with everything but the unreserved characters percent-encoded,
+/// for use in a query string.
+pub fn encode_component(text: &str) -> String {
+    encode(text.as_bytes(), b"")
+}
+
+/// Percent-encodes the bytes other than the unreserved characters and
+/// `keep`.
+fn encode(bytes: &[u8], keep: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len());
+    for b in bytes {
+        match b {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(*b as char)
+            }
+            b if keep.contains(b) => encoded.push(*b as char),
+            _ => encoded.push_str(&format!("%{b:02X}")),
+        }
+    }
+    encoded
+}
+
+/// The last segment of `uri`, decoded, such as the file name.
+pub fn file_name(uri: &str) -> String {
+    let segment = uri.trim_end_matches('/').rsplit('/').next().unwrap_or(uri);
+    match decode(segment) {
+        Some(bytes) => OsStr::from_bytes(&bytes).to_string_lossy().into_owned(),
+        None => segment.to_string(),
+    }
+}
+
+fn decode(s: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut iter = s.bytes();
+    while let Some(b) = iter.next() {
+        if b == b'%' {
+            let hex = [iter.next()?, iter.next()?];
+            let hex = std::str::from_utf8(&hex).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+        } else {
+            bytes.push(b);
+        }
+    }
+    Some(bytes)
+}